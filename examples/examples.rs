@@ -1,22 +1,30 @@
 use std::time;
 use image::{DynamicImage, ImageFormat};
-use libscreenshot::{get_full_capture_provider, get_area_capture_provider, get_screen_capture_provider, get_window_capture_provider, WindowCaptureProvider};
+use libscreenshot::backend::BackendKind;
+use libscreenshot::shared::{Area, Capability};
+use libscreenshot::{get_full_capture_provider, get_area_capture_provider, get_screen_capture_provider, get_window_capture_provider};
 
 fn main() {
-    window_capture();
+    match std::env::args().nth(1).as_deref() {
+        Some("full") => full_capture(),
+        Some("screen") => current_screen_capture(),
+        Some("area") => area_capture(),
+        Some("capabilities") => capabilities_table(),
+        _ => window_capture(),
+    }
 }
 
 // FullCapture
 fn full_capture() {
     match get_full_capture_provider() {
-        Some(provider) => {
+        Ok(provider) => {
             let r = DynamicImage::from(provider.capture_full().unwrap());
             let output_ext = ("png".to_string()).to_lowercase();
             let output_format = image::ImageFormat::Png;
             write_to_file(output_ext, output_format, r);
         }
-        _ => {
-            println!("Capturing Full failed");
+        Err(error) => {
+            println!("Capturing Full failed: {error}");
         }
     }
 }
@@ -24,35 +32,62 @@ fn full_capture() {
 // ScreenCapture
 fn current_screen_capture() {
     match get_screen_capture_provider() {
-        Some(provider) => {
+        Ok(provider) => {
             let r = DynamicImage::from(provider.capture_current_screen().unwrap());
             let output_ext = ("png".to_string()).to_lowercase();
             let output_format = image::ImageFormat::Png;
             write_to_file(output_ext, output_format, r);
         }
-        _ => {
-            println!("Capturing Screen failed");
+        Err(error) => {
+            println!("Capturing Screen failed: {error}");
         }
     }
 }
 
 // AreaCapture
 fn area_capture() {
-    unimplemented!();
+    match get_area_capture_provider() {
+        Ok(provider) => {
+            let r = DynamicImage::from(provider.capture_area(Area::new(0, 0, 640, 480)).unwrap());
+            let output_ext = ("png".to_string()).to_lowercase();
+            let output_format = image::ImageFormat::Png;
+            write_to_file(output_ext, output_format, r);
+        }
+        Err(error) => {
+            println!("Capturing Area failed: {error}");
+        }
+    }
 }
 
 // WindowCapture for focused window
 fn window_capture() {
     match get_window_capture_provider() {
-        Some(provider) => {
+        Ok(provider) => {
             let r = DynamicImage::from(provider.capture_focused_window().unwrap());
             //let r = DynamicImage::from(provider.capture_window(insert_window_id_here).unwrap());
             let output_ext = ("png".to_string()).to_lowercase();
             let output_format = image::ImageFormat::Png;
             write_to_file(output_ext, output_format, r);
         }
-        _ => {
-            println!("Capturing Window failed");
+        Err(error) => {
+            println!("Capturing Window failed: {error}");
+        }
+    }
+}
+
+// Capabilities of the backends compiled into this build, as a markdown table
+fn capabilities_table() {
+    let columns: Vec<String> = Capability::ALL.iter().map(|c| c.to_string()).collect();
+    println!("| Backend | {} |", columns.join(" | "));
+    println!("| ------- |{}", " --- |".repeat(columns.len()));
+    for kind in BackendKind::ALL {
+        if let Some(backend) = kind.create() {
+            let capabilities = backend.capabilities();
+            let cells: Vec<&str> = Capability::ALL
+                .iter()
+                .map(|c| if capabilities.supports(*c) { "✅" } else { "❌" })
+                .collect();
+            println!("| {} | {} |", kind, cells.join(" | "));
         }
     }
 }
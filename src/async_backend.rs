@@ -0,0 +1,396 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc;
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures_core::Stream;
+use tokio::sync::oneshot;
+
+use crate::{
+    capture::Capture,
+    error::{Error, Result},
+    shared::{
+        Area, Capabilities, CaptureMetadata, CaptureOptions, CaptureTarget, CursorImage,
+        DesktopCapture, RawFrame, Screen, ScreenId, Screenshot, StreamId, WindowId, WindowInfo,
+    },
+    stream::{Frame, Pacer},
+    traits::CaptureBackend,
+    ImageBuffer,
+};
+
+type Job = Box<dyn FnOnce(&dyn CaptureBackend) + Send>;
+
+/// Async counterpart of [`CaptureBackend`], running the backend on a dedicated capture thread.
+///
+/// Requests are served one at a time in the order they were made, so libraries like Xlib are only
+/// ever called from that thread and never block the executor. Dropping a pending future cancels
+/// its request unless the capture thread already started it; a running capture finishes and its
+/// result is discarded.
+///
+/// The handle is cheap to clone. The capture thread exits once all clones are dropped.
+#[derive(Clone)]
+pub struct AsyncBackend {
+    jobs: mpsc::Sender<Job>,
+    name: &'static str,
+    capabilities: Capabilities,
+    timeout: Option<Duration>,
+}
+
+impl AsyncBackend {
+    /// Moves `provider` onto a new capture thread.
+    pub fn new<P: CaptureBackend + 'static>(provider: P) -> Self {
+        let name = provider.name();
+        let capabilities = provider.capabilities();
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        std::thread::Builder::new()
+            .name("libscreenshot-capture".to_string())
+            .spawn(move || {
+                for job in receiver {
+                    job(&provider);
+                }
+            })
+            .expect("failed to spawn capture thread");
+        AsyncBackend {
+            jobs,
+            name,
+            capabilities,
+            timeout: None,
+        }
+    }
+
+    /// Fails calls with [`Error::Timeout`] if they take longer than `timeout`, including the time
+    /// spent waiting for earlier requests. Needs a tokio runtime with the time driver enabled.
+    ///
+    /// A timed out call that has not started yet is skipped. A call the capture thread is
+    /// already running cannot be interrupted: it finishes before later requests are served, and
+    /// its result is discarded.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Runs `f` with the backend on the capture thread.
+    pub async fn call<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn CaptureBackend) -> Result<T> + Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let job: Job = Box::new(move |backend| {
+            // Requests cancelled while queued are skipped.
+            if !sender.is_closed() {
+                let _ = sender.send(f(backend));
+            }
+        });
+        self.jobs.send(job).map_err(|_| Self::stopped())?;
+        let result = async { receiver.await.map_err(|_| Self::stopped())? };
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, result)
+                .await
+                .map_err(|_| Error::Timeout(timeout))?,
+            None => result.await,
+        }
+    }
+
+    /// The capture thread only stops early if the backend panicked.
+    fn stopped() -> Error {
+        Error::backend("async", "capture thread stopped")
+    }
+
+    pub async fn connect(&self) -> Result<()> {
+        self.call(|backend| backend.connect()).await
+    }
+
+    pub async fn capture_window(&self, window_id: WindowId) -> Result<ImageBuffer> {
+        self.call(move |backend| backend.capture_window(window_id))
+            .await
+    }
+
+    pub async fn capture_focused_window(&self) -> Result<ImageBuffer> {
+        self.call(|backend| backend.capture_focused_window()).await
+    }
+
+    pub async fn capture_screen(&self, screen_id: ScreenId) -> Result<ImageBuffer> {
+        self.call(move |backend| backend.capture_screen(screen_id))
+            .await
+    }
+
+    pub async fn capture_current_screen(&self) -> Result<ImageBuffer> {
+        self.call(|backend| backend.capture_current_screen()).await
+    }
+
+    pub async fn capture_area(&self, area: Area) -> Result<ImageBuffer> {
+        self.call(move |backend| backend.capture_area(area)).await
+    }
+
+    pub async fn capture_full(&self) -> Result<ImageBuffer> {
+        self.call(|backend| backend.capture_full()).await
+    }
+
+    pub async fn list_windows(&self) -> Result<Vec<WindowInfo>> {
+        self.call(|backend| backend.list_windows()).await
+    }
+
+    pub async fn list_screens(&self) -> Result<Vec<Screen>> {
+        self.call(|backend| backend.list_screens()).await
+    }
+
+    pub async fn capture(&self, target: &CaptureTarget) -> Result<ImageBuffer> {
+        let target = *target;
+        self.call(move |backend| backend.capture(&target)).await
+    }
+
+    pub async fn capture_with_metadata(&self, target: &CaptureTarget) -> Result<Screenshot> {
+        let target = *target;
+        self.call(move |backend| backend.capture_with_metadata(&target))
+            .await
+    }
+
+    pub async fn capture_with_options(
+        &self,
+        target: &CaptureTarget,
+        options: &CaptureOptions,
+    ) -> Result<Screenshot> {
+        let (target, options) = (*target, *options);
+        self.call(move |backend| backend.capture_with_options(&target, &options))
+            .await
+    }
+
+    /// Captures in the backend's native pixel format. The frame is always owned, since the
+    /// backend's buffer stays on the capture thread.
+    pub async fn capture_raw(&self, target: &CaptureTarget) -> Result<RawFrame<'static>> {
+        let target = *target;
+        self.call(move |backend| backend.capture_raw(&target)).await
+    }
+
+    pub async fn capture_cursor(&self) -> Result<CursorImage> {
+        self.call(|backend| backend.capture_cursor()).await
+    }
+
+    pub async fn capture_desktop(&self) -> Result<DesktopCapture> {
+        self.call(|backend| backend.capture_desktop()).await
+    }
+
+    /// Runs a [`Capture`], waiting for its delay without holding up the capture thread. Captures
+    /// with a delay need a tokio runtime with the time driver enabled.
+    pub async fn run(&self, capture: &Capture) -> Result<Screenshot> {
+        capture.validate()?;
+        let (delay, capture) = capture.take_delay();
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        self.call(move |backend| capture.run(backend)).await
+    }
+
+    /// Runs a [`Capture`] like [`run`](Self::run), returning the frame in its requested format.
+    /// Captures with a delay need a tokio runtime with the time driver enabled.
+    pub async fn run_raw(&self, capture: &Capture) -> Result<(RawFrame<'static>, CaptureMetadata)> {
+        capture.validate()?;
+        let (delay, capture) = capture.take_delay();
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        self.call(move |backend| capture.run_raw(backend)).await
+    }
+
+    /// Streams `target` at `fps` frames per second, like a [`FrameStream`](crate::stream::FrameStream).
+    ///
+    /// Frames are paced on the async side and captured one at a time, so other requests are
+    /// served in between. Needs a tokio runtime with the time driver enabled, even without a
+    /// timeout.
+    pub fn frames(&self, target: CaptureTarget, fps: f64) -> AsyncFrames {
+        AsyncFrames {
+            backend: self.clone(),
+            id: StreamId::unique(),
+            target,
+            pacer: Pacer::new(fps),
+            started: Instant::now(),
+            sequence: 0,
+            pending_dropped: 0,
+            total_dropped: 0,
+            spare: None,
+            pending: None,
+            stopped: false,
+        }
+    }
+}
+
+type PendingFrame =
+    Pin<Box<dyn Future<Output = (Duration, Result<(ImageBuffer, Result<Vec<Area>>)>)> + Send>>;
+
+/// Frames of a target captured through an [`AsyncBackend`].
+///
+/// Slots missed while the consumer or the capture thread lagged behind are reported through
+/// [`Frame::dropped`]; failed and timed out captures are yielded as errors without ending the
+/// stream. If the capture thread stopped, that error is yielded once and the stream ends.
+/// Dropping the stream cancels the frame in flight.
+pub struct AsyncFrames {
+    backend: AsyncBackend,
+    id: StreamId,
+    target: CaptureTarget,
+    pacer: Pacer,
+    started: Instant,
+    sequence: u64,
+    pending_dropped: u64,
+    total_dropped: u64,
+    spare: Option<ImageBuffer>,
+    pending: Option<PendingFrame>,
+    stopped: bool,
+}
+
+impl AsyncFrames {
+    pub fn target(&self) -> CaptureTarget {
+        self.target
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.pacer.interval()
+    }
+
+    /// Total number of frame slots dropped so far.
+    pub fn dropped(&self) -> u64 {
+        self.total_dropped
+    }
+
+    /// Hands a frame's image back so a later capture can reuse its allocation.
+    pub fn recycle(&mut self, image: ImageBuffer) {
+        self.spare = Some(image);
+    }
+
+    /// Waits for the next frame, for callers that do not use `Stream` combinators.
+    pub async fn next_frame(&mut self) -> Result<Frame> {
+        std::future::poll_fn(|cx| self.poll_frame(cx)).await
+    }
+
+    fn poll_frame(&mut self, cx: &mut Context<'_>) -> Poll<Result<Frame>> {
+        if self.stopped {
+            return Poll::Ready(Err(AsyncBackend::stopped()));
+        }
+        let pending = self.pending.get_or_insert_with(|| {
+            let (slot, missed) = self.pacer.next_slot(Instant::now());
+            self.pending_dropped += missed;
+            let backend = self.backend.clone();
+            let (id, target) = (self.id, self.target);
+            let started = self.started;
+            let mut image = self.spare.take().unwrap_or_else(|| ImageBuffer::new(0, 0));
+            Box::pin(async move {
+                tokio::time::sleep_until(slot.into()).await;
+                let timestamp = started.elapsed();
+                let result = backend
+                    .call(move |backend| {
+                        let dirty = backend.capture_changes(id, &target, &mut image);
+                        Ok((image, dirty))
+                    })
+                    .await;
+                (timestamp, result)
+            })
+        });
+        let (timestamp, result) = ready!(pending.as_mut().poll(cx));
+        self.pending = None;
+
+        let (image, dirty) = match result {
+            Ok((image, Ok(dirty))) => (image, dirty),
+            Ok((image, Err(error))) => {
+                self.spare = Some(image);
+                return Poll::Ready(Err(error));
+            }
+            Err(error @ Error::Timeout(_)) => return Poll::Ready(Err(error)),
+            // Frame jobs always succeed, so anything else means the capture thread is gone.
+            Err(error) => {
+                self.stopped = true;
+                return Poll::Ready(Err(error));
+            }
+        };
+        let frame = Frame {
+            image,
+            timestamp,
+            sequence: self.sequence,
+            dropped: std::mem::take(&mut self.pending_dropped),
+            dirty,
+        };
+        self.sequence += 1;
+        self.total_dropped += frame.dropped;
+        Poll::Ready(Ok(frame))
+    }
+}
+
+impl Drop for AsyncFrames {
+    fn drop(&mut self) {
+        // Queued behind the frame in flight, if any, so that one is not tracked anew.
+        let id = self.id;
+        let _ = self
+            .backend
+            .jobs
+            .send(Box::new(move |backend| backend.end_stream(id)));
+    }
+}
+
+impl Stream for AsyncFrames {
+    type Item = Result<Frame>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.stopped {
+            return Poll::Ready(None);
+        }
+        self.poll_frame(cx).map(Some)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::platform::mock::MockProvider;
+    use crate::traits::Provider;
+
+    async fn next(frames: &mut AsyncFrames) -> Option<Result<Frame>> {
+        std::future::poll_fn(|cx| Pin::new(&mut *frames).poll_next(cx)).await
+    }
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn frames_end_when_the_capture_thread_stops() {
+        let provider = MockProvider::new().with_fault(|_| panic!("backend panicked"));
+        let backend = AsyncBackend::new(provider);
+        let mut frames = backend.frames(CaptureTarget::Full, 0.0);
+        runtime().block_on(async {
+            assert!(matches!(
+                next(&mut frames).await,
+                Some(Err(Error::Backend { .. }))
+            ));
+            assert!(next(&mut frames).await.is_none());
+            assert!(next(&mut frames).await.is_none());
+        });
+    }
+
+    #[test]
+    fn frames_continue_after_timeouts() {
+        let provider = MockProvider::new().with_latency(Duration::from_millis(50));
+        let backend = AsyncBackend::new(provider).with_timeout(Duration::from_millis(10));
+        let mut frames = backend.frames(CaptureTarget::Area(Area::new(0, 0, 4, 4)), 0.0);
+        runtime().block_on(async {
+            assert!(matches!(
+                next(&mut frames).await,
+                Some(Err(Error::Timeout(_)))
+            ));
+            assert!(matches!(
+                next(&mut frames).await,
+                Some(Err(Error::Timeout(_)))
+            ));
+        });
+    }
+}
@@ -0,0 +1,307 @@
+use std::{env, fmt, str::FromStr};
+
+#[cfg(any(
+    all(
+        target_os = "linux",
+        any(
+            feature = "linux_xorg",
+            feature = "linux_wayland",
+            feature = "linux_portal"
+        )
+    ),
+    all(target_os = "windows", feature = "windows", feature = "windows_gdi"),
+    all(target_os = "macos", feature = "macos")
+))]
+use crate::traits::Provider;
+use crate::{
+    error::{Error, Result},
+    shared::Capability,
+    traits::CaptureBackend,
+};
+
+/// Environment variable that forces a backend, e.g. `LIBSCREENSHOT_BACKEND=portal`.
+pub const BACKEND_ENV_VAR: &str = "LIBSCREENSHOT_BACKEND";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BackendKind {
+    X11,
+    Wayland,
+    Portal,
+    Gdi,
+    CoreGraphics,
+}
+
+impl BackendKind {
+    pub const ALL: [BackendKind; 5] = [
+        BackendKind::X11,
+        BackendKind::Wayland,
+        BackendKind::Portal,
+        BackendKind::Gdi,
+        BackendKind::CoreGraphics,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BackendKind::X11 => "x11",
+            BackendKind::Wayland => "wayland",
+            BackendKind::Portal => "portal",
+            BackendKind::Gdi => "gdi",
+            BackendKind::CoreGraphics => "coregraphics",
+        }
+    }
+
+    /// Creates the backend without connecting, or `None` if it is not compiled into this build.
+    pub fn create(self) -> Option<Box<dyn CaptureBackend>> {
+        match self {
+            #[cfg(all(target_os = "linux", feature = "linux_xorg"))]
+            BackendKind::X11 => Some(Box::new(crate::platform::linux::X11Provider::new())),
+            #[cfg(all(target_os = "linux", feature = "linux_wayland"))]
+            BackendKind::Wayland => Some(Box::new(crate::platform::linux::WaylandProvider::new())),
+            #[cfg(all(target_os = "linux", feature = "linux_portal"))]
+            BackendKind::Portal => Some(Box::new(crate::platform::linux::PortalProvider::new())),
+            #[cfg(all(target_os = "windows", feature = "windows", feature = "windows_gdi"))]
+            BackendKind::Gdi => Some(Box::new(crate::platform::windows::GdiProvider::new())),
+            #[cfg(all(target_os = "macos", feature = "macos"))]
+            BackendKind::CoreGraphics => Some(Box::new(crate::platform::macos::CGProvider::new())),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// Whether support for this backend is compiled into the current build.
+    pub fn is_available(self) -> bool {
+        match self {
+            BackendKind::X11 => cfg!(all(target_os = "linux", feature = "linux_xorg")),
+            BackendKind::Wayland => cfg!(all(target_os = "linux", feature = "linux_wayland")),
+            BackendKind::Portal => cfg!(all(target_os = "linux", feature = "linux_portal")),
+            BackendKind::Gdi => cfg!(all(
+                target_os = "windows",
+                feature = "windows",
+                feature = "windows_gdi"
+            )),
+            BackendKind::CoreGraphics => cfg!(all(target_os = "macos", feature = "macos")),
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        BackendKind::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| Error::UnknownBackend(s.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SessionType {
+    X11,
+    Wayland,
+    #[default]
+    Unknown,
+}
+
+/// Description of the graphical session the process runs in.
+#[derive(Debug, Clone, Default)]
+pub struct SessionInfo {
+    pub session_type: SessionType,
+    pub display: Option<String>,
+    pub wayland_display: Option<String>,
+}
+
+impl SessionInfo {
+    /// Reads `XDG_SESSION_TYPE`, `DISPLAY` and `WAYLAND_DISPLAY`.
+    pub fn detect() -> Self {
+        let var = |name| env::var(name).ok().filter(|value| !value.is_empty());
+        let session_type = match var("XDG_SESSION_TYPE").as_deref() {
+            Some("x11") => SessionType::X11,
+            Some("wayland") => SessionType::Wayland,
+            _ => SessionType::Unknown,
+        };
+        SessionInfo {
+            session_type,
+            display: var("DISPLAY"),
+            wayland_display: var("WAYLAND_DISPLAY"),
+        }
+    }
+
+    pub fn is_wayland(&self) -> bool {
+        match self.session_type {
+            SessionType::Wayland => true,
+            SessionType::X11 => false,
+            SessionType::Unknown => self.wayland_display.is_some(),
+        }
+    }
+}
+
+/// Chooses a capture backend at runtime.
+///
+/// Backends are tried in order of preference for the detected session; the first one that can
+/// actually connect (display reachable, required extensions or protocols present) wins.
+#[derive(Debug, Clone)]
+pub struct BackendSelector {
+    session: SessionInfo,
+    preferred: Option<BackendKind>,
+    /// A [`BACKEND_ENV_VAR`] value that names no backend, reported when opening.
+    unknown: Option<String>,
+    fallback: bool,
+}
+
+impl Default for BackendSelector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BackendSelector {
+    /// Detects the session and honours [`BACKEND_ENV_VAR`]. Opening fails with
+    /// [`Error::UnknownBackend`] if it names no backend, unless one is set with
+    /// [`with_backend`](Self::with_backend).
+    pub fn new() -> Self {
+        Self::with_preference(
+            env::var(BACKEND_ENV_VAR)
+                .ok()
+                .filter(|name| !name.is_empty()),
+        )
+    }
+
+    fn with_preference(name: Option<String>) -> Self {
+        let (preferred, unknown) = match name.as_deref().map(str::parse) {
+            Some(Ok(kind)) => (Some(kind), None),
+            Some(Err(_)) => (None, name),
+            None => (None, None),
+        };
+        BackendSelector {
+            session: SessionInfo::detect(),
+            preferred,
+            unknown,
+            fallback: true,
+        }
+    }
+
+    pub fn with_session(mut self, session: SessionInfo) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Tries `kind` first, overriding [`BACKEND_ENV_VAR`].
+    pub fn with_backend(mut self, kind: BackendKind) -> Self {
+        self.preferred = Some(kind);
+        self.unknown = None;
+        self
+    }
+
+    /// Only tries the preferred backend, if one is set.
+    pub fn without_fallback(mut self) -> Self {
+        self.fallback = false;
+        self
+    }
+
+    pub fn session(&self) -> &SessionInfo {
+        &self.session
+    }
+
+    /// Backends to try, in order.
+    pub fn candidates(&self) -> Vec<BackendKind> {
+        let detected = if cfg!(target_os = "windows") {
+            vec![BackendKind::Gdi]
+        } else if cfg!(target_os = "macos") {
+            vec![BackendKind::CoreGraphics]
+        } else if self.session.is_wayland() {
+            // XWayland only sees X clients, so it is the last resort.
+            vec![BackendKind::Wayland, BackendKind::Portal, BackendKind::X11]
+        } else {
+            vec![BackendKind::X11, BackendKind::Wayland, BackendKind::Portal]
+        };
+
+        let fallback = match self.preferred {
+            Some(_) if !self.fallback => Vec::new(),
+            _ => detected,
+        };
+        let mut candidates = Vec::new();
+        for kind in self.preferred.into_iter().chain(fallback) {
+            if kind.is_available() && !candidates.contains(&kind) {
+                candidates.push(kind);
+            }
+        }
+        candidates
+    }
+
+    /// Opens the first candidate that connects successfully. Fails with the connection error
+    /// of the most preferred candidate if none does.
+    pub fn open(&self) -> Result<Box<dyn CaptureBackend>> {
+        self.open_first(None)
+    }
+
+    /// Opens the first candidate that supports `capability` and connects successfully.
+    pub fn open_with(&self, capability: Capability) -> Result<Box<dyn CaptureBackend>> {
+        self.open_first(Some(capability))
+    }
+
+    fn open_first(&self, capability: Option<Capability>) -> Result<Box<dyn CaptureBackend>> {
+        if let Some(name) = &self.unknown {
+            return Err(Error::UnknownBackend(name.clone()));
+        }
+        let mut first_error = None;
+        for backend in self
+            .candidates()
+            .into_iter()
+            .filter_map(BackendKind::create)
+        {
+            if capability.is_some_and(|capability| !backend.capabilities().supports(capability)) {
+                continue;
+            }
+            match backend.connect() {
+                Ok(()) => return Ok(backend),
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+        Err(first_error.unwrap_or(match capability {
+            Some(capability) => Error::Unsupported(capability),
+            None => Error::NoBackend,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_backend_names_are_reported() {
+        let selector = BackendSelector::with_preference(Some("x1l".to_string()));
+        assert!(matches!(
+            selector.open(),
+            Err(Error::UnknownBackend(name)) if name == "x1l"
+        ));
+        assert!(matches!(
+            selector.open_with(Capability::FullCapture),
+            Err(Error::UnknownBackend(_))
+        ));
+        let selector = selector
+            .with_backend(BackendKind::Portal)
+            .without_fallback();
+        // Checked without opening, which would connect to the session bus.
+        assert!(selector.unknown.is_none());
+        assert_eq!(selector.preferred, Some(BackendKind::Portal));
+        assert!(selector
+            .candidates()
+            .iter()
+            .all(|&kind| kind == BackendKind::Portal));
+
+        let selector = BackendSelector::with_preference(Some(" Wayland".to_string()));
+        assert_eq!(selector.preferred, Some(BackendKind::Wayland));
+        assert!(BackendSelector::with_preference(None).unknown.is_none());
+    }
+}
@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use image::imageops::{self, FilterType};
+
+use crate::{
+    error::{Error, Result},
+    shared::{
+        Area, CaptureMetadata, CaptureOptions, CaptureTarget, PixelFormat, RawFrame, ScreenId,
+        Screenshot, WindowId,
+    },
+    traits::CaptureBackend,
+};
+
+/// A capture request with its options, run against any backend.
+///
+/// Requests are plain values, so they can be stored, reused and (with the `serde` feature)
+/// serialized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capture {
+    target: CaptureTarget,
+    options: CaptureOptions,
+    scale: f64,
+    delay: Duration,
+    format: Option<PixelFormat>,
+}
+
+impl Capture {
+    pub fn new(target: CaptureTarget) -> Self {
+        Capture {
+            target,
+            options: CaptureOptions::default(),
+            scale: 1.0,
+            delay: Duration::ZERO,
+            format: None,
+        }
+    }
+
+    pub fn window(window_id: WindowId) -> Self {
+        Self::new(CaptureTarget::Window(window_id))
+    }
+
+    pub fn focused_window() -> Self {
+        Self::new(CaptureTarget::FocusedWindow)
+    }
+
+    pub fn window_under_cursor() -> Self {
+        Self::new(CaptureTarget::WindowUnderCursor)
+    }
+
+    pub fn screen(screen_id: ScreenId) -> Self {
+        Self::new(CaptureTarget::Screen(screen_id))
+    }
+
+    pub fn current_screen() -> Self {
+        Self::new(CaptureTarget::CurrentScreen)
+    }
+
+    pub fn area(area: Area) -> Self {
+        Self::new(CaptureTarget::Area(area))
+    }
+
+    pub fn full() -> Self {
+        Self::new(CaptureTarget::Full)
+    }
+
+    /// Draws the mouse pointer into the capture.
+    pub fn with_cursor(mut self) -> Self {
+        self.options.cursor = true;
+        self
+    }
+
+    /// Includes window-manager decorations in window captures.
+    pub fn with_decorations(mut self) -> Self {
+        self.options.decorations = true;
+        self
+    }
+
+    /// Resizes the captured image by `factor`. The metadata keeps describing the captured area.
+    ///
+    /// Running the capture fails with [`Error::InvalidScaleFactor`] unless `factor` is a
+    /// positive, finite number.
+    pub fn scale(mut self, factor: f64) -> Self {
+        self.scale = factor;
+        self
+    }
+
+    /// Pixel layout of the frames returned by [`run_raw`](Self::run_raw). Defaults to RGBA.
+    pub fn format(mut self, format: PixelFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Waits for `delay` before capturing, e.g. to give a menu time to open.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn target(&self) -> CaptureTarget {
+        self.target
+    }
+
+    pub fn options(&self) -> CaptureOptions {
+        self.options
+    }
+
+    /// Rejects invalid options before waiting for the delay.
+    pub(crate) fn validate(&self) -> Result<()> {
+        match self.scale.is_finite() && self.scale > 0.0 {
+            true => Ok(()),
+            false => Err(Error::InvalidScaleFactor(self.scale)),
+        }
+    }
+
+    /// Splits off the delay, for callers that wait for it themselves.
+    #[cfg(feature = "async")]
+    pub(crate) fn take_delay(self) -> (Duration, Self) {
+        (
+            self.delay,
+            Capture {
+                delay: Duration::ZERO,
+                ..self
+            },
+        )
+    }
+
+    /// Captures with `provider`, blocking for the delay first.
+    pub fn run<P: CaptureBackend + ?Sized>(&self, provider: &P) -> Result<Screenshot> {
+        self.validate()?;
+        if !self.delay.is_zero() {
+            std::thread::sleep(self.delay);
+        }
+        let mut screenshot = provider.capture_with_options(&self.target, &self.options)?;
+        if self.scale != 1.0 {
+            let image = &screenshot.image;
+            let width = (image.width() as f64 * self.scale).round().max(1.0) as u32;
+            let height = (image.height() as f64 * self.scale).round().max(1.0) as u32;
+            screenshot.image = imageops::resize(image, width, height, FilterType::Triangle);
+        }
+        Ok(screenshot)
+    }
+
+    /// Like [`run`](Self::run), but converts the image to the pixel layout set with
+    /// [`format`](Self::format), e.g. for an encoder that expects BGRA.
+    pub fn run_raw<P: CaptureBackend + ?Sized>(
+        &self,
+        provider: &P,
+    ) -> Result<(RawFrame<'static>, CaptureMetadata)> {
+        let Screenshot { image, metadata } = self.run(provider)?;
+        let frame = RawFrame::from(image);
+        match self.format {
+            Some(format) if format != frame.format() => Ok((frame.convert(format)?, metadata)),
+            _ => Ok((frame, metadata)),
+        }
+    }
+}
+
+impl From<CaptureTarget> for Capture {
+    fn from(target: CaptureTarget) -> Self {
+        Self::new(target)
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::platform::mock::{MockProvider, Pattern};
+    use crate::traits::Provider;
+    use image::Rgba;
+
+    fn provider() -> MockProvider {
+        MockProvider::new().with_pattern(Pattern::Solid(Rgba([10, 20, 30, 255])))
+    }
+
+    #[test]
+    fn invalid_scale_fails_without_capturing() {
+        let provider = provider();
+        for factor in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                Capture::full().scale(factor).run(&provider),
+                Err(Error::InvalidScaleFactor(_))
+            ));
+        }
+        assert_eq!(provider.calls(), 0);
+
+        let screenshot = Capture::area(Area::new(0, 0, 100, 51))
+            .scale(0.5)
+            .run(&provider)
+            .unwrap();
+        assert_eq!(screenshot.image.dimensions(), (50, 26));
+        assert_eq!(screenshot.metadata.area, Area::new(0, 0, 100, 51));
+    }
+
+    #[test]
+    fn run_raw_converts_to_the_requested_format() {
+        let provider = provider();
+        let capture = Capture::area(Area::new(0, 0, 2, 1));
+        let (frame, metadata) = capture.run_raw(&provider).unwrap();
+        assert_eq!(frame.format(), PixelFormat::RGBA8888);
+        assert_eq!(metadata.area, Area::new(0, 0, 2, 1));
+
+        let (frame, _) = capture
+            .format(PixelFormat::BGR888)
+            .run_raw(&provider)
+            .unwrap();
+        assert_eq!(frame.format(), PixelFormat::BGR888);
+        assert_eq!(frame.stride(), 6);
+        assert_eq!(frame.data(), [30, 20, 10, 30, 20, 10]);
+    }
+}
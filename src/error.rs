@@ -1,9 +1,89 @@
+use crate::shared::{Area, Capability, ScreenId, WindowId};
+
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("{0}")]
     AreaIntConversionError(#[from] std::num::TryFromIntError),
-    #[error("Window capture failed.")]
-    WindowCaptureFailed,
+    #[error("Display {} is unavailable.", name.as_deref().unwrap_or("(default)"))]
+    DisplayUnavailable { name: Option<String> },
+    #[error("Connection to the display was lost.")]
+    ConnectionLost,
+    #[error("Window {0:#x} not found.")]
+    WindowNotFound(WindowId),
+    #[error("Window {0:#x} is not viewable.")]
+    WindowNotViewable(WindowId),
+    #[error("No window is focused.")]
+    NoFocusedWindow,
+    #[error("No window is under the pointer.")]
+    NoWindowUnderCursor,
+    #[error("Screen {0} not found.")]
+    ScreenNotFound(ScreenId),
+    #[error("Unsupported pixel format (depth: {depth}, bpp: {bpp}, masks: {masks:#x?}).")]
+    UnsupportedPixelFormat {
+        depth: u32,
+        bpp: u32,
+        masks: [u64; 3],
+    },
+    #[error("Area {area:?} is out of bounds {bounds:?}.")]
+    AreaOutOfBounds { area: Area, bounds: Area },
+    #[error("Image data does not match its dimensions ({width}x{height}).")]
+    InvalidImageData { width: u32, height: u32 },
+    #[error("X protocol error {error_code} ({description}) in request {request_code}.{minor_code} on resource {resource_id:#x}.")]
+    XProtocol {
+        error_code: u8,
+        request_code: u8,
+        minor_code: u8,
+        resource_id: u64,
+        description: String,
+    },
+    #[error("{0} is not supported by this provider.")]
+    Unsupported(Capability),
+    #[error("Scale factor {0} is not a positive, finite number.")]
+    InvalidScaleFactor(f64),
+    #[error("Capture was cancelled by the user.")]
+    Cancelled,
+    #[error("Capture timed out after {0:?}.")]
+    Timeout(std::time::Duration),
+    #[error("Unknown backend `{0}`.")]
+    UnknownBackend(String),
+    #[error("No capture backend is available.")]
+    NoBackend,
+    #[error("{backend}: {source}")]
+    Backend {
+        backend: &'static str,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+impl Error {
+    // Only unused in builds without any backend that fails with backend-specific errors.
+    #[cfg_attr(
+        not(any(
+            feature = "async",
+            feature = "replay",
+            all(
+                target_os = "linux",
+                any(
+                    feature = "linux_xorg",
+                    feature = "linux_wayland",
+                    feature = "linux_portal"
+                )
+            ),
+            all(target_os = "windows", feature = "windows", feature = "windows_gdi"),
+        )),
+        allow(dead_code)
+    )]
+    pub(crate) fn backend<E>(backend: &'static str, source: E) -> Self
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Error::Backend {
+            backend,
+            source: source.into(),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -1,51 +1,59 @@
-mod error;
+#[cfg(feature = "async")]
+pub mod async_backend;
+pub mod backend;
+pub mod capture;
+pub mod error;
 pub mod platform;
 pub mod prelude;
 pub mod shared;
+pub mod stream;
 pub mod traits;
 
 pub type ImageBuffer = image::ImageBuffer<image::Rgba<u8>, Vec<u8>>;
 
 pub use traits::*;
 
-#[allow(unreachable_code)]
-pub fn get_capture_provider() -> Option<impl Provider> {
-    #[cfg(target_os = "linux")]
-    return Some(platform::linux::X11Provider::new());
-    #[cfg(target_os = "windows")]
-    return Some(platform::windows::GdiProvider::new());
-    #[cfg(target_os = "macos")]
-    return Some(platform::macos::CGProvider::new());
-    return None;
+use backend::BackendSelector;
+use error::Result;
+use shared::Capability;
+
+/// Returns the best available backend for the current session, or why none could be opened.
+pub fn get_capture_provider() -> Result<Box<dyn CaptureBackend>> {
+    BackendSelector::new().open()
+}
+
+pub fn get_window_capture_provider() -> Result<Box<dyn WindowCaptureProvider>> {
+    BackendSelector::new()
+        .open_with(Capability::WindowCapture)
+        .map(|backend| backend as _)
+}
+
+pub fn get_window_enumeration_provider() -> Result<Box<dyn WindowEnumerationProvider>> {
+    BackendSelector::new()
+        .open_with(Capability::WindowEnumeration)
+        .map(|backend| backend as _)
 }
 
-#[allow(unreachable_code)]
-pub fn get_window_capture_provider() -> Option<impl WindowCaptureProvider> {
-    #[cfg(target_os = "linux")]
-    return Some(platform::linux::X11Provider::new());
-    #[cfg(target_os = "windows")]
-    return Some(platform::windows::GdiProvider::new());
-    #[cfg(target_os = "macos")]
-    return Some(platform::macos::CGProvider::new());
-    return None;
+pub fn get_screen_capture_provider() -> Result<Box<dyn ScreenCaptureProvider>> {
+    BackendSelector::new()
+        .open_with(Capability::ScreenCapture)
+        .map(|backend| backend as _)
 }
 
-#[allow(unreachable_code)]
-pub fn get_screen_capture_provider() -> Option<Box<dyn ScreenCaptureProvider>> {
-    None
+pub fn get_screen_enumeration_provider() -> Result<Box<dyn ScreenEnumerationProvider>> {
+    BackendSelector::new()
+        .open_with(Capability::ScreenEnumeration)
+        .map(|backend| backend as _)
 }
 
-#[allow(unreachable_code)]
-pub fn get_area_capture_provider() -> Option<Box<dyn AreaCaptureProvider>> {
-    #[cfg(target_os = "linux")]
-    return Some(Box::new(platform::linux::X11Provider::new()));
-    return None;
+pub fn get_area_capture_provider() -> Result<Box<dyn AreaCaptureProvider>> {
+    BackendSelector::new()
+        .open_with(Capability::AreaCapture)
+        .map(|backend| backend as _)
 }
 
-#[allow(unreachable_code)]
-pub fn get_full_capture_provider() -> Option<Box<dyn FullCaptureProvider>> {
-    #[cfg(target_os = "linux")]
-    return Some(Box::new(platform::linux::X11Provider::new()));
-    #[allow(unreachable_code)]
-    return None;
+pub fn get_full_capture_provider() -> Result<Box<dyn FullCaptureProvider>> {
+    BackendSelector::new()
+        .open_with(Capability::FullCapture)
+        .map(|backend| backend as _)
 }
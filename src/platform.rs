@@ -1,8 +1,17 @@
 #[cfg(all(feature = "windows", target_os = "windows"))]
 pub mod windows;
 
-#[cfg(all(feature = "linux", target_os = "linux"))]
+#[cfg(all(
+    any(feature = "linux_xorg", feature = "linux_wayland", feature = "linux_portal"),
+    target_os = "linux"
+))]
 pub mod linux;
 
 #[cfg(all(feature = "macos", target_os = "macos"))]
 pub mod macos;
+
+#[cfg(feature = "mock")]
+pub mod mock;
+
+#[cfg(feature = "replay")]
+pub mod replay;
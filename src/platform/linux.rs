@@ -3,3 +3,15 @@ mod x11_provider;
 
 #[cfg(feature = "linux_xorg")]
 pub use self::x11_provider::X11Provider;
+
+#[cfg(feature = "linux_wayland")]
+mod wayland_provider;
+
+#[cfg(feature = "linux_wayland")]
+pub use self::wayland_provider::WaylandProvider;
+
+#[cfg(feature = "linux_portal")]
+mod portal_provider;
+
+#[cfg(feature = "linux_portal")]
+pub use self::portal_provider::PortalProvider;
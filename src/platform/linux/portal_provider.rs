@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures_lite::{future, StreamExt};
+use zbus::blocking::{connection, fdo::DBusProxy, Connection, Proxy};
+use zbus::proxy::SignalStream;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+use crate::{error::*, shared::*, traits::*, ImageBuffer};
+
+const BACKEND: &str = "portal";
+
+const PORTAL_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const SCREENSHOT_INTERFACE: &str = "org.freedesktop.portal.Screenshot";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn portal_error<E: std::error::Error + Send + Sync + 'static>(error: E) -> Error {
+    Error::backend(BACKEND, error)
+}
+
+/// Returns a handle token that is unique within this process.
+fn next_handle_token() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    format!(
+        "libscreenshot_{}_{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Object path the portal will use for the request created with `token`.
+fn request_path(connection: &Connection, token: &str) -> Result<String> {
+    let sender = connection
+        .unique_name()
+        .ok_or_else(|| Error::backend(BACKEND, "connection has no unique bus name"))?;
+    let sender = sender.trim_start_matches(':').replace('.', "_");
+    Ok(format!("{PORTAL_PATH}/request/{sender}/{token}"))
+}
+
+/// Converts a `file://` URI into a local path, decoding percent escapes.
+fn uri_to_path(uri: &str) -> Result<PathBuf> {
+    let encoded = uri
+        .strip_prefix("file://")
+        .ok_or_else(|| Error::backend(BACKEND, format!("unsupported screenshot uri: {uri}")))?;
+    // Skip an optional authority component ("file://localhost/...").
+    let encoded = &encoded[encoded.find('/').unwrap_or(0)..];
+
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut input = encoded.bytes();
+    while let Some(byte) = input.next() {
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+        let hex = [input.next(), input.next()];
+        let decoded = match hex {
+            [Some(hi), Some(lo)] => std::str::from_utf8(&[hi, lo])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        bytes.push(
+            decoded.ok_or_else(|| {
+                Error::backend(BACKEND, format!("malformed screenshot uri: {uri}"))
+            })?,
+        );
+    }
+
+    use std::os::unix::ffi::OsStringExt;
+    Ok(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+}
+
+/// Captures through the `org.freedesktop.portal.Screenshot` interface of xdg-desktop-portal.
+///
+/// This is the only sanctioned capture method on GNOME and KDE Wayland sessions. The portal
+/// may ask the user for permission on first use and writes the screenshot to a file, usually in
+/// the user's pictures folder, which is loaded into the returned image and left in place.
+///
+/// Interactive captures are only available through
+/// [`capture_interactive`](Self::capture_interactive), not through [`CaptureBackend`], so they
+/// need a `PortalProvider` rather than a backend picked by
+/// [`BackendSelector`](crate::backend::BackendSelector).
+pub struct PortalProvider {
+    connection: Mutex<Option<Connection>>,
+    bus_address: Option<String>,
+    timeout: Duration,
+}
+
+impl Default for PortalProvider {
+    fn default() -> Self {
+        Self {
+            connection: Mutex::default(),
+            bus_address: None,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl Provider for PortalProvider {
+    fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl PortalProvider {
+    /// Creates a provider talking to the bus at `address` instead of the session bus.
+    ///
+    /// Useful for running against a mock portal service on a private bus.
+    pub fn with_bus_address(address: impl Into<String>) -> Self {
+        Self {
+            bus_address: Some(address.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Fails captures with [`Error::Timeout`] if the portal has not answered within `timeout`,
+    /// including the time the user spends in a permission or selection dialog. Defaults to one
+    /// minute.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Lets the user pick the captured area (or window) through the portal's own dialog.
+    ///
+    /// There is no equivalent on [`CaptureBackend`], since no other backend has such a dialog.
+    pub fn capture_interactive(&self) -> Result<ImageBuffer> {
+        self.screenshot(true)
+    }
+
+    fn open_bus(&self) -> Result<Connection> {
+        match &self.bus_address {
+            Some(address) => connection::Builder::address(address.as_str())
+                .and_then(|builder| builder.build())
+                .map_err(portal_error),
+            None => Connection::session().map_err(portal_error),
+        }
+    }
+
+    /// Runs `f` on the provider's bus connection, reconnecting after failures.
+    fn with_connection<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        let mut connection = self.connection.lock().unwrap_or_else(|e| e.into_inner());
+        let current = match connection.take() {
+            Some(current) => current,
+            None => self.open_bus()?,
+        };
+        let result = f(&current);
+        if !matches!(result, Err(Error::Backend { .. })) {
+            *connection = Some(current);
+        }
+        result
+    }
+
+    fn screenshot(&self, interactive: bool) -> Result<ImageBuffer> {
+        let path = self.with_connection(|connection| {
+            let token = next_handle_token();
+
+            // Subscribe before issuing the call so the response cannot be missed.
+            let mut request = Proxy::new(
+                connection,
+                PORTAL_DESTINATION,
+                request_path(connection, &token)?,
+                REQUEST_INTERFACE,
+            )
+            .map_err(portal_error)?;
+            let mut responses = Self::responses(&request)?;
+
+            let portal = Proxy::new(
+                connection,
+                PORTAL_DESTINATION,
+                PORTAL_PATH,
+                SCREENSHOT_INTERFACE,
+            )
+            .map_err(portal_error)?;
+            let options = HashMap::from([
+                ("handle_token", Value::from(token.as_str())),
+                ("interactive", Value::from(interactive)),
+                ("modal", Value::from(interactive)),
+            ]);
+            let handle: OwnedObjectPath = portal
+                .call("Screenshot", &("", options))
+                .map_err(portal_error)?;
+
+            // Portals older than version 0.9 pick their own request path.
+            if handle.as_str() != request.path().as_str() {
+                request = Proxy::new(
+                    connection,
+                    PORTAL_DESTINATION,
+                    handle.into_inner(),
+                    REQUEST_INTERFACE,
+                )
+                .map_err(portal_error)?;
+                responses = Self::responses(&request)?;
+            }
+
+            let message = match self.wait_for_response(&mut responses) {
+                Err(Error::Timeout(timeout)) => {
+                    // Dismiss any dialog the portal still shows.
+                    let _ = request.call_method("Close", &());
+                    return Err(Error::Timeout(timeout));
+                }
+                message => message?,
+            };
+            let (response, mut results): (u32, HashMap<String, OwnedValue>) =
+                message.body().deserialize().map_err(portal_error)?;
+
+            match response {
+                0 => {}
+                1 => return Err(Error::Cancelled),
+                _ => return Err(Error::backend(BACKEND, "screenshot request failed")),
+            }
+
+            let uri = results
+                .remove("uri")
+                .and_then(|uri| String::try_from(uri).ok())
+                .ok_or_else(|| Error::backend(BACKEND, "portal response did not contain a uri"))?;
+            uri_to_path(&uri)
+        })?;
+
+        Ok(image::open(&path).map_err(portal_error)?.to_rgba8())
+    }
+
+    fn responses(request: &Proxy) -> Result<SignalStream<'static>> {
+        async_io::block_on(request.inner().receive_signal("Response")).map_err(portal_error)
+    }
+
+    /// Waits for the `Response` signal of a request, up to the provider's timeout.
+    fn wait_for_response(&self, responses: &mut SignalStream) -> Result<zbus::Message> {
+        let response = async {
+            responses
+                .next()
+                .await
+                .ok_or_else(|| Error::backend(BACKEND, "portal request ended without a response"))
+        };
+        let timeout = async {
+            async_io::Timer::after(self.timeout).await;
+            Err(Error::Timeout(self.timeout))
+        };
+        async_io::block_on(future::or(response, timeout))
+    }
+}
+
+impl FullCaptureProvider for PortalProvider {
+    fn capture_full(&self) -> Result<ImageBuffer> {
+        self.screenshot(false)
+    }
+}
+
+impl WindowCaptureProvider for PortalProvider {
+    fn capture_window(&self, _window_id: WindowId) -> Result<ImageBuffer> {
+        Err(Error::Unsupported(Capability::WindowCapture))
+    }
+
+    fn capture_focused_window(&self) -> Result<ImageBuffer> {
+        Err(Error::Unsupported(Capability::WindowCapture))
+    }
+}
+
+impl ScreenCaptureProvider for PortalProvider {
+    fn capture_screen(&self, _screen_id: ScreenId) -> Result<ImageBuffer> {
+        Err(Error::Unsupported(Capability::ScreenCapture))
+    }
+
+    fn capture_current_screen(&self) -> Result<ImageBuffer> {
+        Err(Error::Unsupported(Capability::ScreenCapture))
+    }
+}
+
+impl AreaCaptureProvider for PortalProvider {
+    fn capture_area(&self, _area: Area) -> Result<ImageBuffer> {
+        Err(Error::Unsupported(Capability::AreaCapture))
+    }
+}
+
+impl WindowEnumerationProvider for PortalProvider {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>> {
+        Err(Error::Unsupported(Capability::WindowEnumeration))
+    }
+}
+
+impl ScreenEnumerationProvider for PortalProvider {
+    fn list_screens(&self) -> Result<Vec<Screen>> {
+        Err(Error::Unsupported(Capability::ScreenEnumeration))
+    }
+}
+
+impl CaptureBackend for PortalProvider {
+    fn name(&self) -> &'static str {
+        BACKEND
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            window_capture: false,
+            screen_capture: false,
+            area_capture: false,
+            full_capture: true,
+            window_enumeration: false,
+            screen_enumeration: false,
+            cursor: false,
+            decorations: false,
+            alpha: false,
+            streaming: false,
+            multi_monitor: false,
+        }
+    }
+
+    fn connect(&self) -> Result<()> {
+        self.with_connection(|connection| {
+            let bus = DBusProxy::new(connection).map_err(portal_error)?;
+            match bus.name_has_owner(PORTAL_DESTINATION.try_into().map_err(portal_error)?) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(Error::backend(BACKEND, "xdg-desktop-portal is not running")),
+                Err(error) => Err(portal_error(error)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+
+    use zbus::message::Header;
+
+    /// A `dbus-daemon` serving a private bus for the duration of a test.
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        fn spawn() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.as_mut()?)
+                .read_line(&mut address)
+                .ok()?;
+            Some(PrivateBus {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    /// How the fake portal answers the next request.
+    enum Answer {
+        File(PathBuf),
+        Cancel,
+        Ignore,
+    }
+
+    struct FakePortal {
+        answers: Mutex<Vec<Answer>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.portal.Screenshot")]
+    impl FakePortal {
+        async fn screenshot(
+            &self,
+            #[zbus(header)] header: Header<'_>,
+            #[zbus(connection)] connection: &zbus::Connection,
+            _parent_window: &str,
+            options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            let sender = header
+                .sender()
+                .unwrap()
+                .trim_start_matches(':')
+                .replace('.', "_");
+            let token = String::try_from(options["handle_token"].try_clone().unwrap()).unwrap();
+            let path = format!("{PORTAL_PATH}/request/{sender}/{token}");
+            let answer = self.answers.lock().unwrap().remove(0);
+            let (response, results) = match answer {
+                Answer::File(file) => (
+                    0u32,
+                    HashMap::from([("uri", Value::from(format!("file://{}", file.display())))]),
+                ),
+                Answer::Cancel => (1, HashMap::new()),
+                Answer::Ignore => return Ok(OwnedObjectPath::try_from(path).unwrap()),
+            };
+            connection
+                .emit_signal(
+                    None::<&str>,
+                    path.as_str(),
+                    REQUEST_INTERFACE,
+                    "Response",
+                    &(response, results),
+                )
+                .await?;
+            Ok(OwnedObjectPath::try_from(path).unwrap())
+        }
+    }
+
+    #[test]
+    fn uri_to_path_decodes() {
+        assert_eq!(
+            uri_to_path("file:///tmp/Screenshot%20from%202024.png").unwrap(),
+            PathBuf::from("/tmp/Screenshot from 2024.png")
+        );
+        assert_eq!(
+            uri_to_path("file://localhost/home/%C3%A9.png").unwrap(),
+            PathBuf::from("/home/\u{e9}.png")
+        );
+        for uri in [
+            "https://example.com/a.png",
+            "file:///a%2",
+            "file:///a%zz.png",
+        ] {
+            assert!(uri_to_path(uri).is_err(), "{uri}");
+        }
+    }
+
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn screenshot_through_fake_portal() {
+        let bus = PrivateBus::spawn().expect("failed to start dbus-daemon");
+        let file = std::env::temp_dir().join(format!("{}.png", next_handle_token()));
+        ImageBuffer::from_pixel(3, 2, image::Rgba([1, 2, 3, 255]))
+            .save(&file)
+            .unwrap();
+        let portal = FakePortal {
+            answers: Mutex::new(vec![
+                Answer::Cancel,
+                Answer::File(file.clone()),
+                Answer::Ignore,
+            ]),
+        };
+        let _service = connection::Builder::address(bus.address.as_str())
+            .unwrap()
+            .name(PORTAL_DESTINATION)
+            .unwrap()
+            .serve_at(PORTAL_PATH, portal)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let provider =
+            PortalProvider::with_bus_address(&bus.address).with_timeout(Duration::from_millis(200));
+        provider.connect().unwrap();
+
+        assert!(matches!(provider.capture_full(), Err(Error::Cancelled)));
+
+        let image = provider.capture_full().unwrap();
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.get_pixel(2, 1).0, [1, 2, 3, 255]);
+        assert!(file.exists());
+        let _ = std::fs::remove_file(&file);
+
+        assert!(matches!(
+            provider.capture_full(),
+            Err(Error::Timeout(timeout)) if timeout == Duration::from_millis(200)
+        ));
+    }
+}
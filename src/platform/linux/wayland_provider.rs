@@ -0,0 +1,1015 @@
+use std::os::fd::{AsFd, AsRawFd, FromRawFd, OwnedFd};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use wayland_client::{
+    backend::WaylandError,
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::{wl_buffer, wl_output, wl_registry, wl_shm, wl_shm_pool},
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum,
+};
+use wayland_protocols::xdg::xdg_output::zv1::client::{
+    zxdg_output_manager_v1::ZxdgOutputManagerV1,
+    zxdg_output_v1::{self, ZxdgOutputV1},
+};
+use wayland_protocols_wlr::screencopy::v1::client::{
+    zwlr_screencopy_frame_v1::{self, ZwlrScreencopyFrameV1},
+    zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1,
+};
+
+use crate::{error::*, shared::*, traits::*, ImageBuffer};
+
+const BACKEND: &str = "wayland";
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Screen captured for [`CaptureTarget::CurrentScreen`]: the first output, listed as primary.
+const CURRENT_SCREEN: ScreenId = 0;
+
+fn protocol_error<E: std::error::Error + Send + Sync + 'static>(error: E) -> Error {
+    Error::backend(BACKEND, error)
+}
+
+#[derive(Default, Clone)]
+struct OutputInfo {
+    name: Option<String>,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    refresh_mhz: i32,
+    scale: i32,
+    transform: Option<wl_output::Transform>,
+    /// Position and size in compositor coordinates as reported by xdg-output, which unlike
+    /// `scale` accounts for fractional scaling.
+    logical_position: Option<(i32, i32)>,
+    logical_size: Option<(i32, i32)>,
+}
+
+impl OutputInfo {
+    /// Size of the current mode, rotated like the output.
+    fn physical_size(&self) -> (u32, u32) {
+        let (w, h) = (self.width.max(0) as u32, self.height.max(0) as u32);
+        match self.transform {
+            Some(
+                wl_output::Transform::_90
+                | wl_output::Transform::_270
+                | wl_output::Transform::Flipped90
+                | wl_output::Transform::Flipped270,
+            ) => (h, w),
+            _ => (w, h),
+        }
+    }
+
+    /// Size of the output in compositor (logical) coordinates.
+    fn logical_size(&self) -> (u32, u32) {
+        if let Some((w, h)) = self.logical_size {
+            return (w.max(0) as u32, h.max(0) as u32);
+        }
+        let scale = self.scale.max(1) as u32;
+        let (w, h) = self.physical_size();
+        (w / scale, h / scale)
+    }
+
+    fn area(&self) -> Area {
+        let (x, y) = self.logical_position.unwrap_or((self.x, self.y));
+        let (w, h) = self.logical_size();
+        Area::new(x as i64, y as i64, w as u64, h as u64)
+    }
+
+    /// Physical pixels per logical pixel, e.g. 1.5 for a fractionally scaled output.
+    fn scale_factor(&self) -> f64 {
+        match (self.physical_size(), self.logical_size()) {
+            ((physical, _), (logical, _)) if physical > 0 && logical > 0 => {
+                physical as f64 / logical as f64
+            }
+            _ => self.scale.max(1) as f64,
+        }
+    }
+}
+
+struct Output {
+    /// Registry name of the `wl_output` global.
+    global: u32,
+    output: wl_output::WlOutput,
+    xdg_output: Option<ZxdgOutputV1>,
+    info: OutputInfo,
+}
+
+#[derive(Clone, Copy)]
+struct BufferSpec {
+    format: wl_shm::Format,
+    width: u32,
+    height: u32,
+    stride: u32,
+}
+
+#[derive(Default)]
+struct FrameState {
+    buffers: Vec<BufferSpec>,
+    buffer_done: bool,
+    y_invert: bool,
+    ready: bool,
+    failed: bool,
+}
+
+#[derive(Default)]
+struct State {
+    outputs: Vec<Output>,
+    xdg_output_manager: Option<ZxdgOutputManagerV1>,
+    /// Set when an output was added, so its description still has to be fetched.
+    outputs_added: bool,
+    frame: FrameState,
+}
+
+impl State {
+    fn add_output(
+        &mut self,
+        registry: &wl_registry::WlRegistry,
+        global: u32,
+        version: u32,
+        qh: &QueueHandle<Self>,
+    ) {
+        let output = registry.bind::<wl_output::WlOutput, _, _>(global, version.min(4), qh, global);
+        let xdg_output = self
+            .xdg_output_manager
+            .as_ref()
+            .map(|manager| manager.get_xdg_output(&output, qh, global));
+        self.outputs.push(Output {
+            global,
+            output,
+            xdg_output,
+            info: OutputInfo::default(),
+        });
+        self.outputs_added = true;
+    }
+
+    fn remove_output(&mut self, global: u32) {
+        let Some(index) = self.outputs.iter().position(|o| o.global == global) else {
+            return;
+        };
+        let output = self.outputs.remove(index);
+        if let Some(xdg_output) = output.xdg_output {
+            xdg_output.destroy();
+        }
+        if output.output.version() >= 3 {
+            output.output.release();
+        }
+    }
+
+    fn output_info(&mut self, global: u32) -> Option<&mut OutputInfo> {
+        self.outputs
+            .iter_mut()
+            .find(|output| output.global == global)
+            .map(|output| &mut output.info)
+    }
+}
+
+/// Tracks outputs that are plugged in or removed after connecting.
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for State {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } if interface == wl_output::WlOutput::interface().name => {
+                state.add_output(registry, name, version, qh)
+            }
+            wl_registry::Event::GlobalRemove { name } => state.remove_output(name),
+            _ => (),
+        }
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, u32> for State {
+    fn event(
+        state: &mut Self,
+        _: &wl_output::WlOutput,
+        event: wl_output::Event,
+        global: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(info) = state.output_info(*global) else {
+            return;
+        };
+        match event {
+            wl_output::Event::Geometry {
+                x, y, transform, ..
+            } => {
+                info.x = x;
+                info.y = y;
+                info.transform = transform.into_result().ok();
+            }
+            wl_output::Event::Mode {
+                flags,
+                width,
+                height,
+                refresh,
+            } => {
+                let current = match flags {
+                    WEnum::Value(flags) => flags.contains(wl_output::Mode::Current),
+                    WEnum::Unknown(_) => false,
+                };
+                if current {
+                    info.width = width;
+                    info.height = height;
+                    info.refresh_mhz = refresh;
+                }
+            }
+            wl_output::Event::Scale { factor } => info.scale = factor,
+            wl_output::Event::Name { name } => info.name = Some(name),
+            _ => (),
+        }
+    }
+}
+
+impl Dispatch<ZxdgOutputV1, u32> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZxdgOutputV1,
+        event: zxdg_output_v1::Event,
+        global: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(info) = state.output_info(*global) else {
+            return;
+        };
+        match event {
+            zxdg_output_v1::Event::LogicalPosition { x, y } => info.logical_position = Some((x, y)),
+            zxdg_output_v1::Event::LogicalSize { width, height } => {
+                info.logical_size = Some((width, height))
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Dispatch<ZwlrScreencopyFrameV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrScreencopyFrameV1,
+        event: zwlr_screencopy_frame_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let frame = &mut state.frame;
+        match event {
+            zwlr_screencopy_frame_v1::Event::Buffer {
+                format: WEnum::Value(format),
+                width,
+                height,
+                stride,
+            } => frame.buffers.push(BufferSpec {
+                format,
+                width,
+                height,
+                stride,
+            }),
+            zwlr_screencopy_frame_v1::Event::Flags {
+                flags: WEnum::Value(flags),
+            } => frame.y_invert = flags.contains(zwlr_screencopy_frame_v1::Flags::YInvert),
+            zwlr_screencopy_frame_v1::Event::BufferDone => frame.buffer_done = true,
+            zwlr_screencopy_frame_v1::Event::Ready { .. } => frame.ready = true,
+            zwlr_screencopy_frame_v1::Event::Failed => frame.failed = true,
+            _ => (),
+        }
+    }
+}
+
+macro_rules! ignore_events {
+    ($($interface:ty),*) => {
+        $(
+            impl Dispatch<$interface, ()> for State {
+                fn event(
+                    _: &mut Self,
+                    _: &$interface,
+                    _: <$interface as Proxy>::Event,
+                    _: &(),
+                    _: &Connection,
+                    _: &QueueHandle<Self>,
+                ) {
+                }
+            }
+        )*
+    };
+}
+
+ignore_events!(
+    wl_shm::WlShm,
+    wl_shm_pool::WlShmPool,
+    wl_buffer::WlBuffer,
+    ZwlrScreencopyManagerV1,
+    ZxdgOutputManagerV1
+);
+
+/// Anonymous shared memory the compositor copies a frame into.
+struct ShmFile {
+    fd: OwnedFd,
+    len: usize,
+}
+
+impl ShmFile {
+    fn new(len: usize) -> Result<Self> {
+        unsafe {
+            let fd = libc::memfd_create(c"libscreenshot".as_ptr(), libc::MFD_CLOEXEC);
+            if fd < 0 {
+                return Err(protocol_error(std::io::Error::last_os_error()));
+            }
+            let fd = OwnedFd::from_raw_fd(fd);
+            if libc::ftruncate(fd.as_raw_fd(), len as libc::off_t) < 0 {
+                return Err(protocol_error(std::io::Error::last_os_error()));
+            }
+            Ok(ShmFile { fd, len })
+        }
+    }
+
+    fn read(&self) -> Result<Vec<u8>> {
+        unsafe {
+            let ptr = libc::mmap(
+                std::ptr::null_mut(),
+                self.len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                self.fd.as_raw_fd(),
+                0,
+            );
+            if ptr == libc::MAP_FAILED {
+                return Err(protocol_error(std::io::Error::last_os_error()));
+            }
+            let data = std::slice::from_raw_parts(ptr as *const u8, self.len).to_vec();
+            libc::munmap(ptr, self.len);
+            Ok(data)
+        }
+    }
+}
+
+/// A connection with the globals needed for wlr-screencopy bound.
+struct Session {
+    queue: EventQueue<State>,
+    state: State,
+    shm: wl_shm::WlShm,
+    manager: ZwlrScreencopyManagerV1,
+    timeout: Duration,
+}
+
+impl Session {
+    fn connect(timeout: Duration) -> Result<Self> {
+        let conn = Connection::connect_to_env().map_err(protocol_error)?;
+        let (globals, queue) = registry_queue_init::<State>(&conn).map_err(protocol_error)?;
+        let qh = queue.handle();
+        let shm = globals
+            .bind::<wl_shm::WlShm, _, _>(&qh, 1..=1, ())
+            .map_err(protocol_error)?;
+        let manager = globals
+            .bind::<ZwlrScreencopyManagerV1, _, _>(&qh, 1..=3, ())
+            .map_err(|_| Error::backend(BACKEND, "compositor does not support wlr-screencopy"))?;
+        let mut state = State {
+            // Without xdg-output, logical sizes are derived from the integer output scale.
+            xdg_output_manager: globals.bind(&qh, 1..=3, ()).ok(),
+            ..State::default()
+        };
+        for global in globals.contents().clone_list() {
+            if global.interface == wl_output::WlOutput::interface().name {
+                state.add_output(globals.registry(), global.name, global.version, &qh);
+            }
+        }
+        let mut session = Session {
+            queue,
+            state,
+            shm,
+            manager,
+            timeout,
+        };
+        session.refresh()?;
+        Ok(session)
+    }
+
+    /// Processes output hot-plugging since the last call and waits for new outputs to be
+    /// described.
+    fn refresh(&mut self) -> Result<()> {
+        self.queue
+            .roundtrip(&mut self.state)
+            .map_err(protocol_error)?;
+        while std::mem::take(&mut self.state.outputs_added) {
+            self.queue
+                .roundtrip(&mut self.state)
+                .map_err(protocol_error)?;
+        }
+        Ok(())
+    }
+
+    fn output(&self, index: usize) -> Option<&Output> {
+        self.state.outputs.get(index)
+    }
+
+    fn dispatch_until(&mut self, done: impl Fn(&FrameState) -> bool) -> Result<()> {
+        dispatch_until(&mut self.queue, &mut self.state, self.timeout, done)
+    }
+
+    /// Captures an output, or a region of it given in output-local logical coordinates.
+    fn capture(
+        &mut self,
+        index: usize,
+        region: Option<GenericArea<i32, i32>>,
+    ) -> Result<ImageBuffer> {
+        let qh = self.queue.handle();
+        let output = match self.output(index) {
+            Some(output) => output.output.clone(),
+            None => return Err(Error::ScreenNotFound(index as ScreenId)),
+        };
+        let frame = match region {
+            None => self.manager.capture_output(0, &output, &qh, ()),
+            Some(r) => {
+                self.manager
+                    .capture_output_region(0, &output, r.x, r.y, r.width, r.height, &qh, ())
+            }
+        };
+        self.state.frame = FrameState::default();
+        // Version 3 frames announce every supported buffer type before `buffer_done`.
+        let version = frame.version();
+        let result = self
+            .dispatch_until(|f| f.buffer_done || (version < 3 && !f.buffers.is_empty()))
+            .and_then(|_| self.copy_frame(&frame));
+        frame.destroy();
+        result
+    }
+
+    fn copy_frame(&mut self, frame: &ZwlrScreencopyFrameV1) -> Result<ImageBuffer> {
+        let qh = self.queue.handle();
+        let spec = self
+            .state
+            .frame
+            .buffers
+            .iter()
+            .copied()
+            .find(|spec| pixel_layout(spec.format).is_some())
+            .ok_or_else(|| Error::backend(BACKEND, "no supported shm buffer format offered"))?;
+        let len = spec.stride as usize * spec.height as usize;
+        let file = ShmFile::new(len)?;
+        let pool = self.shm.create_pool(file.fd.as_fd(), len as i32, &qh, ());
+        let buffer = pool.create_buffer(
+            0,
+            spec.width as i32,
+            spec.height as i32,
+            spec.stride as i32,
+            spec.format,
+            &qh,
+            (),
+        );
+        frame.copy(&buffer);
+        let result = self
+            .dispatch_until(|f| f.ready)
+            .and_then(|_| file.read())
+            .and_then(|data| convert(&data, spec, self.state.frame.y_invert));
+        buffer.destroy();
+        pool.destroy();
+        result
+    }
+}
+
+/// Dispatches frame events until `done` or the frame failed, failing with [`Error::Timeout`] if
+/// the compositor does not get there within `timeout`.
+fn dispatch_until(
+    queue: &mut EventQueue<State>,
+    state: &mut State,
+    timeout: Duration,
+    done: impl Fn(&FrameState) -> bool,
+) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        queue.dispatch_pending(state).map_err(protocol_error)?;
+        if done(&state.frame) || state.frame.failed {
+            break;
+        }
+        queue.flush().map_err(protocol_error)?;
+        // Events may have been queued by another reader in the meantime.
+        let Some(guard) = queue.prepare_read() else {
+            continue;
+        };
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let mut fd = libc::pollfd {
+            fd: guard.connection_fd().as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let millis = remaining
+            .as_nanos()
+            .div_ceil(1_000_000)
+            .min(i32::MAX as u128) as i32;
+        match unsafe { libc::poll(&mut fd, 1, millis) } {
+            0 => return Err(Error::Timeout(timeout)),
+            n if n < 0 => {
+                let error = std::io::Error::last_os_error();
+                if error.kind() != std::io::ErrorKind::Interrupted {
+                    return Err(protocol_error(error));
+                }
+            }
+            _ => match guard.read() {
+                Err(WaylandError::Io(error)) if error.kind() == std::io::ErrorKind::WouldBlock => {}
+                result => {
+                    result.map_err(protocol_error)?;
+                }
+            },
+        }
+    }
+    match state.frame.failed {
+        true => Err(Error::backend(
+            BACKEND,
+            "compositor failed to copy the frame",
+        )),
+        false => Ok(()),
+    }
+}
+
+/// Returns `(red, green, blue, alpha)` byte offsets for the little-endian shm formats we handle.
+fn pixel_layout(format: wl_shm::Format) -> Option<(usize, usize, usize, Option<usize>)> {
+    match format {
+        wl_shm::Format::Argb8888 => Some((2, 1, 0, Some(3))),
+        wl_shm::Format::Xrgb8888 => Some((2, 1, 0, None)),
+        wl_shm::Format::Abgr8888 => Some((0, 1, 2, Some(3))),
+        wl_shm::Format::Xbgr8888 => Some((0, 1, 2, None)),
+        _ => None,
+    }
+}
+
+fn convert(data: &[u8], spec: BufferSpec, y_invert: bool) -> Result<ImageBuffer> {
+    let (r, g, b, a) = pixel_layout(spec.format)
+        .ok_or_else(|| Error::backend(BACKEND, "unsupported shm buffer format"))?;
+    let image = ImageBuffer::from_fn(spec.width, spec.height, |x, y| {
+        let row = if y_invert { spec.height - 1 - y } else { y };
+        let i = row as usize * spec.stride as usize + x as usize * 4;
+        let px = &data[i..i + 4];
+        image::Rgba([px[r], px[g], px[b], a.map_or(0xFF, |a| px[a])])
+    });
+    Ok(image)
+}
+
+/// Whether the union of `rects` contains all of `area`.
+fn covers(rects: &[Area], area: Area) -> bool {
+    let mut uncovered = vec![area];
+    for rect in rects {
+        uncovered = uncovered
+            .into_iter()
+            .flat_map(|part| subtract(part, rect))
+            .collect();
+    }
+    uncovered.is_empty()
+}
+
+/// The parts of `area` outside of `cut`, as up to four rectangles.
+fn subtract(area: Area, cut: &Area) -> Vec<Area> {
+    let Some(inner) = area.intersect(cut) else {
+        return vec![area];
+    };
+    let (right, bottom) = (area.x + area.width as i64, area.y + area.height as i64);
+    let (inner_right, inner_bottom) = (inner.x + inner.width as i64, inner.y + inner.height as i64);
+    [
+        Area::new(area.x, area.y, area.width, (inner.y - area.y) as u64),
+        Area::new(
+            area.x,
+            inner_bottom,
+            area.width,
+            (bottom - inner_bottom) as u64,
+        ),
+        Area::new(area.x, inner.y, (inner.x - area.x) as u64, inner.height),
+        Area::new(
+            inner_right,
+            inner.y,
+            (right - inner_right) as u64,
+            inner.height,
+        ),
+    ]
+    .into_iter()
+    .filter(|part| part.width > 0 && part.height > 0)
+    .collect()
+}
+
+/// Capture backend for wlroots-based compositors, using the wlr-screencopy protocol.
+///
+/// Screen ids map to the index of a `wl_output` in the order the compositor advertised them, and
+/// shift when outputs are unplugged. Outputs are captured at their native resolution; full and
+/// area captures are composed in logical coordinates, which follow fractional scaling if the
+/// compositor supports xdg-output. Area captures have to lie entirely on outputs, while full
+/// captures fill the parts of the desktop's bounding box that no output shows with a background.
+pub struct WaylandProvider {
+    session: Mutex<Option<Session>>,
+    timeout: Duration,
+    background: Option<image::Rgba<u8>>,
+}
+
+impl Default for WaylandProvider {
+    fn default() -> Self {
+        Self {
+            session: Mutex::default(),
+            timeout: DEFAULT_TIMEOUT,
+            background: None,
+        }
+    }
+}
+
+impl Provider for WaylandProvider {
+    fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl WaylandProvider {
+    /// Fails captures with [`Error::Timeout`] if the compositor has not delivered a frame within
+    /// `timeout`. Defaults to five seconds.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Colour for the parts of full captures that no output shows. Defaults to opaque black.
+    pub fn with_background(mut self, background: image::Rgba<u8>) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Runs `f` on the provider's compositor connection, reconnecting after failures.
+    fn with_session<T>(&self, f: impl FnOnce(&mut Session) -> Result<T>) -> Result<T> {
+        let mut session = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        let mut current = match session.take() {
+            Some(mut current) => {
+                current.refresh()?;
+                current
+            }
+            None => Session::connect(self.timeout)?,
+        };
+        let result = f(&mut current);
+        // A timed out frame may still be answered, so it is dropped with its connection.
+        if !matches!(result, Err(Error::Backend { .. } | Error::Timeout(_))) {
+            *session = Some(current);
+        }
+        result
+    }
+
+    fn background(&self) -> image::Rgba<u8> {
+        self.background.unwrap_or(image::Rgba([0, 0, 0, 255]))
+    }
+
+    fn output_areas(session: &Session) -> Vec<Area> {
+        session
+            .state
+            .outputs
+            .iter()
+            .map(|output| output.info.area())
+            .collect()
+    }
+
+    /// Bounding box of all outputs in logical coordinates.
+    fn desktop_area(session: &Session) -> Area {
+        bounding_area(&Self::output_areas(session))
+    }
+}
+
+/// Composes the part of `area` shown by each of `outputs` into one image, with `capture`
+/// returning a region of an output by index. Pixels outside of all outputs get `background`.
+fn compose(
+    outputs: &[Area],
+    area: Area,
+    background: image::Rgba<u8>,
+    mut capture: impl FnMut(usize, GenericArea<i32, i32>) -> Result<ImageBuffer>,
+) -> Result<ImageBuffer> {
+    let mut canvas = ImageBuffer::from_pixel(area.width as u32, area.height as u32, background);
+    for (index, bounds) in outputs.iter().enumerate() {
+        let x0 = area.x.max(bounds.x);
+        let y0 = area.y.max(bounds.y);
+        let x1 = (area.x + area.width as i64).min(bounds.x + bounds.width as i64);
+        let y1 = (area.y + area.height as i64).min(bounds.y + bounds.height as i64);
+        if x1 <= x0 || y1 <= y0 {
+            continue;
+        }
+        let region = GenericArea {
+            x: (x0 - bounds.x) as i32,
+            y: (y0 - bounds.y) as i32,
+            width: (x1 - x0) as i32,
+            height: (y1 - y0) as i32,
+        };
+        let (w, h) = (region.width as u32, region.height as u32);
+        let mut image = capture(index, region)?;
+        if image.dimensions() != (w, h) {
+            image = image::imageops::resize(&image, w, h, image::imageops::FilterType::Triangle);
+        }
+        image::imageops::replace(&mut canvas, &image, x0 - area.x, y0 - area.y);
+    }
+    Ok(canvas)
+}
+
+/// Bounding box of `areas`, or an empty area if there are none.
+fn bounding_area(areas: &[Area]) -> Area {
+    let x0 = areas.iter().map(|a| a.x).min().unwrap_or(0);
+    let y0 = areas.iter().map(|a| a.y).min().unwrap_or(0);
+    let x1 = areas
+        .iter()
+        .map(|a| a.x + a.width as i64)
+        .max()
+        .unwrap_or(0);
+    let y1 = areas
+        .iter()
+        .map(|a| a.y + a.height as i64)
+        .max()
+        .unwrap_or(0);
+    Area::new(x0, y0, (x1 - x0) as u64, (y1 - y0) as u64)
+}
+
+impl ScreenCaptureProvider for WaylandProvider {
+    fn capture_screen(&self, screen_id: ScreenId) -> Result<ImageBuffer> {
+        let index = usize::try_from(screen_id).map_err(|_| Error::ScreenNotFound(screen_id))?;
+        self.with_session(|session| session.capture(index, None))
+    }
+
+    /// Wayland does not expose the pointer position or the focused output, so the first output,
+    /// which is listed as the primary screen, stands in for the current one.
+    fn capture_current_screen(&self) -> Result<ImageBuffer> {
+        self.capture_screen(CURRENT_SCREEN)
+    }
+}
+
+impl ScreenEnumerationProvider for WaylandProvider {
+    fn list_screens(&self) -> Result<Vec<Screen>> {
+        self.with_session(|session| {
+            let screens = session
+                .state
+                .outputs
+                .iter()
+                .enumerate()
+                .map(|(index, Output { info, .. })| Screen {
+                    id: index as ScreenId,
+                    name: info.name.clone().unwrap_or_default(),
+                    area: info.area(),
+                    rotation: match info.transform {
+                        Some(wl_output::Transform::_90 | wl_output::Transform::Flipped90) => {
+                            Rotation::Left
+                        }
+                        Some(wl_output::Transform::_180 | wl_output::Transform::Flipped180) => {
+                            Rotation::Inverted
+                        }
+                        Some(wl_output::Transform::_270 | wl_output::Transform::Flipped270) => {
+                            Rotation::Right
+                        }
+                        _ => Rotation::Normal,
+                    },
+                    // The first advertised output is the closest Wayland has to a primary one.
+                    primary: index == 0,
+                    refresh_rate: match info.refresh_mhz {
+                        0 => None,
+                        mhz => Some(mhz as f64 / 1000.0),
+                    },
+                    scale_factor: info.scale_factor(),
+                })
+                .collect();
+            Ok(screens)
+        })
+    }
+}
+
+impl AreaCaptureProvider for WaylandProvider {
+    fn capture_area(&self, area: Area) -> Result<ImageBuffer> {
+        if area.width == 0 || area.height == 0 {
+            return self.with_session(|session| {
+                Err(Error::AreaOutOfBounds {
+                    area,
+                    bounds: Self::desktop_area(session),
+                })
+            });
+        }
+        let background = self.background();
+        self.with_session(|session| {
+            let outputs = Self::output_areas(session);
+            if !covers(&outputs, area) {
+                return Err(Error::AreaOutOfBounds {
+                    area,
+                    bounds: bounding_area(&outputs),
+                });
+            }
+            compose(&outputs, area, background, |index, region| {
+                session.capture(index, Some(region))
+            })
+        })
+    }
+}
+
+impl FullCaptureProvider for WaylandProvider {
+    fn capture_full(&self) -> Result<ImageBuffer> {
+        let background = self.background();
+        self.with_session(|session| {
+            let outputs = Self::output_areas(session);
+            compose(
+                &outputs,
+                bounding_area(&outputs),
+                background,
+                |index, region| session.capture(index, Some(region)),
+            )
+        })
+    }
+}
+
+impl WindowCaptureProvider for WaylandProvider {
+    fn capture_window(&self, _window_id: WindowId) -> Result<ImageBuffer> {
+        Err(Error::Unsupported(Capability::WindowCapture))
+    }
+
+    fn capture_focused_window(&self) -> Result<ImageBuffer> {
+        Err(Error::Unsupported(Capability::WindowCapture))
+    }
+}
+
+impl WindowEnumerationProvider for WaylandProvider {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>> {
+        Err(Error::Unsupported(Capability::WindowEnumeration))
+    }
+}
+
+impl CaptureBackend for WaylandProvider {
+    fn name(&self) -> &'static str {
+        BACKEND
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            window_capture: false,
+            screen_capture: true,
+            area_capture: true,
+            full_capture: true,
+            window_enumeration: false,
+            screen_enumeration: true,
+            cursor: false,
+            decorations: false,
+            alpha: false,
+            streaming: true,
+            multi_monitor: true,
+        }
+    }
+
+    fn connect(&self) -> Result<()> {
+        self.with_session(|_| Ok(()))
+    }
+
+    fn resolve_target(&self, target: &CaptureTarget) -> Result<CaptureTarget> {
+        match target {
+            CaptureTarget::CurrentScreen => Ok(CaptureTarget::Screen(CURRENT_SCREEN)),
+            target => Ok(*target),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dispatch_until_times_out() {
+        let (client, _server) = std::os::unix::net::UnixStream::pair().unwrap();
+        let connection = Connection::from_socket(client).unwrap();
+        let mut queue = connection.new_event_queue();
+        let mut state = State::default();
+        let timeout = Duration::from_millis(50);
+        let start = Instant::now();
+        assert!(matches!(
+            dispatch_until(&mut queue, &mut state, timeout, |frame| frame.ready),
+            Err(Error::Timeout(t)) if t == timeout
+        ));
+        assert!(start.elapsed() >= timeout);
+
+        state.frame.failed = true;
+        assert!(matches!(
+            dispatch_until(&mut queue, &mut state, timeout, |frame| frame.ready),
+            Err(Error::Backend { .. })
+        ));
+        state.frame = FrameState {
+            ready: true,
+            ..FrameState::default()
+        };
+        assert!(dispatch_until(&mut queue, &mut state, timeout, |frame| frame.ready).is_ok());
+    }
+
+    #[test]
+    fn output_geometry() {
+        // Rotated and scaled by two, without xdg-output.
+        let rotated = OutputInfo {
+            x: 1920,
+            width: 1920,
+            height: 1080,
+            scale: 2,
+            transform: Some(wl_output::Transform::_90),
+            ..OutputInfo::default()
+        };
+        assert_eq!(rotated.physical_size(), (1080, 1920));
+        assert_eq!(rotated.area(), Area::new(1920, 0, 540, 960));
+        assert_eq!(rotated.scale_factor(), 2.0);
+
+        // Fractionally scaled, as described by xdg-output.
+        let fractional = OutputInfo {
+            width: 1920,
+            height: 1080,
+            scale: 2,
+            logical_position: Some((-1280, 0)),
+            logical_size: Some((1280, 720)),
+            ..OutputInfo::default()
+        };
+        assert_eq!(fractional.area(), Area::new(-1280, 0, 1280, 720));
+        assert_eq!(fractional.scale_factor(), 1.5);
+
+        // No mode received yet.
+        assert_eq!(OutputInfo::default().scale_factor(), 1.0);
+    }
+
+    #[test]
+    fn convert_reorders_channels_and_flips() {
+        let spec = BufferSpec {
+            format: wl_shm::Format::Xrgb8888,
+            width: 1,
+            height: 2,
+            stride: 8,
+        };
+        let data = [3, 2, 1, 0, 0, 0, 0, 0, 6, 5, 4, 0, 0, 0, 0, 0];
+        let image = convert(&data, spec, false).unwrap();
+        assert_eq!(image.into_raw(), [1, 2, 3, 255, 4, 5, 6, 255]);
+        let image = convert(&data, spec, true).unwrap();
+        assert_eq!(image.into_raw(), [4, 5, 6, 255, 1, 2, 3, 255]);
+
+        let spec = BufferSpec {
+            format: wl_shm::Format::Abgr8888,
+            ..spec
+        };
+        let image = convert(&data, spec, false).unwrap();
+        assert_eq!(image.into_raw(), [3, 2, 1, 0, 6, 5, 4, 0]);
+        let spec = BufferSpec {
+            format: wl_shm::Format::Rgb565,
+            ..spec
+        };
+        assert!(convert(&data, spec, false).is_err());
+    }
+
+    #[test]
+    fn covers_requires_every_pixel() {
+        let outputs = [Area::new(0, 0, 100, 100), Area::new(100, 20, 50, 50)];
+        assert!(covers(&outputs, Area::new(10, 10, 90, 90)));
+        assert!(covers(&outputs, Area::new(90, 30, 60, 20)));
+        assert!(!covers(&outputs, Area::new(90, 10, 20, 20)));
+        assert!(!covers(&outputs, Area::new(-1, 0, 10, 10)));
+        assert!(!covers(&[], Area::new(0, 0, 1, 1)));
+    }
+
+    #[test]
+    fn compose_fills_uncovered_parts_of_the_desktop() {
+        // A 4x4 output next to a taller 2x6 one, offset upwards.
+        let outputs = [Area::new(0, 0, 4, 4), Area::new(4, -2, 2, 6)];
+        let desktop = bounding_area(&outputs);
+        assert_eq!(desktop, Area::new(0, -2, 6, 6));
+        assert!(!covers(&outputs, desktop));
+
+        let background = image::Rgba([1, 2, 3, 255]);
+        let colors = [image::Rgba([255, 0, 0, 255]), image::Rgba([0, 255, 0, 255])];
+        let mut captured = Vec::new();
+        let image = compose(&outputs, desktop, background, |index, region| {
+            captured.push(index);
+            Ok(ImageBuffer::from_pixel(
+                region.width as u32,
+                region.height as u32,
+                colors[index],
+            ))
+        })
+        .unwrap();
+        assert_eq!(image.dimensions(), (6, 6));
+        assert_eq!(captured, [0, 1]);
+        for y in 0..6 {
+            for x in 0..6 {
+                let expected = match (x, y) {
+                    (4.., _) => colors[1],
+                    (_, 2..) => colors[0],
+                    _ => background,
+                };
+                assert_eq!(*image.get_pixel(x, y), expected, "pixel ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn current_screen_resolves_to_the_first_output() {
+        let provider = WaylandProvider::default();
+        assert_eq!(
+            provider
+                .resolve_target(&CaptureTarget::CurrentScreen)
+                .unwrap(),
+            CaptureTarget::Screen(0)
+        );
+        assert_eq!(
+            provider.resolve_target(&CaptureTarget::Full).unwrap(),
+            CaptureTarget::Full
+        );
+    }
+}
@@ -1,27 +1,95 @@
+use std::ffi::CString;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
 use crate::{error::*, shared::*, traits::*, ImageBuffer};
 
-use image::math::Rect;
-use x11::xlib;
+mod composite;
+mod damage;
+mod error_trap;
+mod shm;
+mod xauth;
 
 mod xutils {
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
     use std::ops::Deref;
-    use x11::xlib;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Instant;
+    use x11::{xfixes, xlib, xrandr};
+
+    use super::composite::{self, Redirection, WindowPixmap};
+    use super::damage::DamageTracker;
+    use super::shm::ShmSegment;
+    use super::xauth::{self, Cookie};
+    use crate::{
+        error::*,
+        shared::{Area, Capability, CursorImage, GenericArea, PixelFormat, RawFrame, StreamId},
+        ImageBuffer,
+    };
+
+    pub struct XDisplayHandle {
+        display: *mut xlib::Display,
+        // Boxed so its address can be handed to Xlib as handler user data.
+        broken: Box<AtomicBool>,
+        shm_supported: Cell<Option<bool>>,
+        shm: RefCell<Option<ShmSegment>>,
+        xfixes_supported: Cell<Option<bool>>,
+        composite: Cell<bool>,
+        composite_supported: Cell<Option<bool>>,
+        redirected: RefCell<HashMap<xlib::Window, Redirection>>,
+        /// Set when a capture stopped at a window that has not been repainted since it was
+        /// redirected, until when to wait for it.
+        repaint_deadline: Cell<Option<Instant>>,
+        damage_event_base: Cell<Option<Option<i32>>>,
+        /// State of every stream capturing through this connection.
+        streams: RefCell<HashMap<StreamId, StreamState>>,
+    }
+
+    #[derive(Default)]
+    struct StreamState {
+        damage: Option<DamagedImage>,
+        /// Where the cursor was drawn into the previous frame, in image coordinates.
+        last_cursor: Option<Area>,
+    }
 
-    use crate::{error::*, shared::GenericArea, ImageBuffer};
+    /// Last image of a damage-tracked rectangle, patched with the damaged parts on every capture.
+    struct DamagedImage {
+        tracker: DamageTracker,
+        rect: Rect,
+        image: ImageBuffer,
+    }
 
-    pub struct XDisplayHandle(*mut xlib::Display);
+    // Xlib connections may be used from any thread as long as access is serialized, which
+    // `X11Provider` guarantees by keeping its connection behind a mutex.
+    unsafe impl Send for XDisplayHandle {}
 
     pub struct XImageHandle(*mut xlib::XImage);
 
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct Rect {
-        x: i32,
-        y: i32,
-        w: u32,
-        h: u32,
+        pub x: i32,
+        pub y: i32,
+        pub w: u32,
+        pub h: u32,
+    }
+
+    pub struct Monitor {
+        pub name: String,
+        /// Root window of the X screen showing the monitor.
+        pub root: xlib::Window,
+        /// Position within `root`.
+        pub rect: Rect,
+        /// Position on the desktop, which spans all X screens.
+        pub desktop: Rect,
+        pub primary: bool,
+        pub rotation: xrandr::Rotation,
+        pub refresh_rate: Option<f64>,
     }
 
     impl Rect {
-        pub fn to_client_coordinates(&self) -> Rect {
+        pub fn to_client_coordinates(self) -> Rect {
             Rect {
                 x: 0,
                 y: 0,
@@ -29,6 +97,26 @@ mod xutils {
                 h: self.h,
             }
         }
+
+        pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+            let x = self.x.max(other.x);
+            let y = self.y.max(other.y);
+            let right = (self.x as i64 + self.w as i64).min(other.x as i64 + other.w as i64);
+            let bottom = (self.y as i64 + self.h as i64).min(other.y as i64 + other.h as i64);
+            (right > x as i64 && bottom > y as i64).then(|| Rect {
+                x,
+                y,
+                w: (right - x as i64) as u32,
+                h: (bottom - y as i64) as u32,
+            })
+        }
+
+        pub fn contains(&self, x: i32, y: i32) -> bool {
+            x >= self.x
+                && y >= self.y
+                && (x as i64) < self.x as i64 + self.w as i64
+                && (y as i64) < self.y as i64 + self.h as i64
+        }
     }
 
     impl From<GenericArea<i32, u32>> for Rect {
@@ -46,7 +134,7 @@ mod xutils {
         type Target = *mut xlib::Display;
 
         fn deref(&self) -> &Self::Target {
-            &self.0
+            &self.display
         }
     }
 
@@ -60,7 +148,20 @@ mod xutils {
 
     impl Drop for XDisplayHandle {
         fn drop(&mut self) {
+            // The segment, damage objects and redirections have to be released while the
+            // connection is still open. Their drawables may be gone already, so errors are
+            // expected.
+            let teardown = || {
+                self.shm.borrow_mut().take();
+                self.streams.borrow_mut().clear();
+                self.redirected.borrow_mut().clear();
+                Ok(())
+            };
             unsafe {
+                let _ = match self.is_broken() {
+                    true => teardown(),
+                    false => self.with_error_trap(teardown),
+                };
                 xlib::XCloseDisplay(**self);
             }
         }
@@ -75,19 +176,57 @@ mod xutils {
     }
 
     impl XDisplayHandle {
-        pub unsafe fn open(name: Option<std::ffi::CString>) -> Result<Self> {
-            let name = match name {
+        pub unsafe fn open(
+            name: Option<std::ffi::CString>,
+            cookie: Option<&Cookie>,
+        ) -> Result<Self> {
+            let name_ptr = match &name {
                 None => std::ptr::null(),
                 Some(cstr) => cstr.as_ptr(),
             };
-            match xlib::XOpenDisplay(name) {
-                d if d.is_null() => Err(Error::WindowCaptureFailed),
-                d => Ok(XDisplayHandle(d)),
+            match xauth::open_display(cookie, || xlib::XOpenDisplay(name_ptr)) {
+                d if d.is_null() => Err(Error::DisplayUnavailable {
+                    name: name
+                        .map(|cstr| cstr.to_string_lossy().into_owned())
+                        .or_else(|| std::env::var("DISPLAY").ok()),
+                }),
+                d => {
+                    let handle = XDisplayHandle {
+                        display: d,
+                        broken: Box::new(AtomicBool::new(false)),
+                        shm_supported: Cell::new(None),
+                        shm: RefCell::new(None),
+                        xfixes_supported: Cell::new(None),
+                        composite: Cell::new(false),
+                        composite_supported: Cell::new(None),
+                        redirected: RefCell::new(HashMap::new()),
+                        repaint_deadline: Cell::new(None),
+                        damage_event_base: Cell::new(None),
+                        streams: RefCell::new(HashMap::new()),
+                    };
+                    super::error_trap::set_io_error_exit_flag(d, &*handle.broken);
+                    Ok(handle)
+                }
             }
         }
 
-        pub unsafe fn open_default_display() -> Result<Self> {
-            Self::open(None)
+        /// Whether the connection was lost due to a fatal I/O error.
+        pub fn is_broken(&self) -> bool {
+            self.broken.load(Ordering::SeqCst)
+        }
+
+        /// Runs `f` while recording X protocol errors on this display.
+        ///
+        /// A recorded protocol error replaces the result of `f`, unless `f` already failed with
+        /// a more specific error than a generic backend failure.
+        pub unsafe fn with_error_trap<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+            let trap = super::error_trap::ErrorTrap::new(**self);
+            let result = f();
+            match (result, trap.check()) {
+                (Err(error), _) if !matches!(error, Error::Backend { .. }) => Err(error),
+                (_, Err(error)) => Err(error),
+                (result, Ok(())) => result,
+            }
         }
 
         pub unsafe fn get_focused_window(&self) -> xlib::Window {
@@ -99,10 +238,12 @@ mod xutils {
             window
         }
 
-        pub unsafe fn get_client_rect(&self, window_id: xlib::Window) -> Rect {
+        pub unsafe fn get_client_rect(&self, window_id: xlib::Window) -> Result<Rect> {
             let mut attrs = std::mem::MaybeUninit::uninit();
 
-            xlib::XGetWindowAttributes(**self, window_id, attrs.as_mut_ptr());
+            if xlib::XGetWindowAttributes(**self, window_id, attrs.as_mut_ptr()) == 0 {
+                return Err(Error::WindowNotFound(window_id));
+            }
 
             let attrs = attrs.assume_init();
             let mut root = 0;
@@ -135,18 +276,105 @@ mod xutils {
                 );
             }
 
-            Rect { x, y, w, h }
+            Ok(Rect { x, y, w, h })
         }
 
+        /// Reads windows from their XComposite pixmap in `get_image`, where supported.
+        pub fn set_composite(&self, composite: bool) {
+            self.composite.set(composite);
+        }
+
+        /// Captures a rectangle of a drawable, preferring MIT-SHM if the server supports it.
+        ///
+        /// Windows other than the root windows of the X screens are read through XComposite if
+        /// enabled, falling back to reading them from the screen.
         pub unsafe fn get_image(
             &self,
             window_id: xlib::Window,
             client_rect: Rect,
         ) -> Result<XImageHandle> {
+            if self.composite.get() && !self.is_root_window(window_id) {
+                match self.with_error_trap(|| self.get_composite_image(window_id, client_rect)) {
+                    Ok(image) => return Ok(image),
+                    // The caller captures again once the window was repainted.
+                    Err(error) if self.repaint_deadline.get().is_some() => return Err(error),
+                    Err(_) => {}
+                }
+            }
+            self.read_image(window_id, window_id, client_rect)
+        }
+
+        unsafe fn get_composite_image(
+            &self,
+            window: xlib::Window,
+            rect: Rect,
+        ) -> Result<XImageHandle> {
+            let supported = self
+                .composite_supported
+                .get()
+                .unwrap_or_else(|| composite::is_supported(**self));
+            self.composite_supported.set(Some(supported));
+            if !supported {
+                return Err(Error::backend("x11-composite", "XComposite unavailable"));
+            }
+            let frame = self.get_frame_window(window);
+            let mut redirected = self.redirected.borrow_mut();
+            redirected.retain(|_, redirection| !redirection.is_destroyed());
+            let redirection = redirected
+                .entry(frame)
+                .or_insert_with(|| self.redirect(frame));
+            if let Some(deadline) = redirection.awaiting_repaint() {
+                self.repaint_deadline.set(Some(deadline));
+                return Err(Error::backend(
+                    "x11-composite",
+                    "window has not been repainted since it was redirected",
+                ));
+            }
+            drop(redirected);
+            let pixmap = WindowPixmap::new(**self, frame)?;
+            let frame_rect = self.get_client_rect(frame)?;
+            let window_rect = self.get_client_rect(window)?;
+            let rect = Rect {
+                x: rect.x + window_rect.x - frame_rect.x,
+                y: rect.y + window_rect.y - frame_rect.y,
+                ..rect
+            };
+            self.read_image(pixmap.pixmap(), frame, rect)
+        }
+
+        /// Redirects a top-level window. The parts that were obscured are undefined in its pixmap
+        /// until its client repaints them, which is observed through XDamage where available;
+        /// without it the window is read right away.
+        unsafe fn redirect(&self, window: xlib::Window) -> Redirection {
+            let tracker = self.damage_event_base().and_then(|event_base| {
+                self.with_error_trap(|| DamageTracker::new(**self, event_base, window))
+                    .ok()
+            });
+            Redirection::new(**self, window, tracker)
+        }
+
+        /// Returns until when to wait before capturing again, if the last capture stopped at a
+        /// window that has not been repainted since it was redirected.
+        pub fn take_repaint_deadline(&self) -> Option<Instant> {
+            self.repaint_deadline.take()
+        }
+
+        /// Reads a rectangle of `drawable`, which has the visual and depth of `window`.
+        unsafe fn read_image(
+            &self,
+            drawable: xlib::Drawable,
+            window: xlib::Window,
+            client_rect: Rect,
+        ) -> Result<XImageHandle> {
+            if let Ok(image) =
+                self.with_error_trap(|| self.get_shm_image(drawable, window, &client_rect))
+            {
+                return Ok(image);
+            }
             const ALL_PLANES: u64 = !0;
             match xlib::XGetImage(
                 **self,
-                window_id,
+                drawable,
                 client_rect.x,
                 client_rect.y,
                 client_rect.w,
@@ -154,11 +382,221 @@ mod xutils {
                 ALL_PLANES,
                 xlib::ZPixmap,
             ) {
-                d if d.is_null() => Err(Error::WindowCaptureFailed),
+                d if d.is_null() => Err(Error::backend("x11", "XGetImage returned no image")),
                 d => Ok(XImageHandle(d)),
             }
         }
 
+        unsafe fn get_shm_image(
+            &self,
+            drawable: xlib::Drawable,
+            window: xlib::Window,
+            rect: &Rect,
+        ) -> Result<XImageHandle> {
+            let supported = self
+                .shm_supported
+                .get()
+                .unwrap_or_else(|| ShmSegment::is_supported(**self));
+            self.shm_supported.set(Some(supported));
+            if !supported {
+                return Err(Error::backend("x11-shm", "MIT-SHM extension unavailable"));
+            }
+            let mut attrs = std::mem::MaybeUninit::<xlib::XWindowAttributes>::zeroed();
+            if xlib::XGetWindowAttributes(**self, window, attrs.as_mut_ptr()) == 0 {
+                return Err(Error::WindowNotFound(window));
+            }
+            let attrs = attrs.assume_init();
+            let mut shm = self.shm.borrow_mut();
+            // ZPixmap images of the depths we convert use at most four bytes per pixel.
+            let required = rect.w as usize * rect.h as usize * 4;
+            let reusable = matches!(&*shm, Some(segment) if segment.capacity() >= required);
+            if !reusable {
+                *shm = None;
+                match ShmSegment::new(**self, required) {
+                    Ok(segment) => *shm = Some(segment),
+                    Err(error) => {
+                        self.shm_supported.set(Some(false));
+                        return Err(error);
+                    }
+                }
+            }
+            let segment = shm.as_mut().expect("segment was just allocated");
+            let image = segment.get_image(drawable, &attrs, rect.x, rect.y, rect.w, rect.h)?;
+            Ok(XImageHandle(image))
+        }
+
+        /// Captures a rectangle of a drawable into `image`, re-reading only the parts damaged
+        /// since the previous call for the same stream, drawable and rectangle.
+        ///
+        /// Returns the changed parts relative to `rect`. Without XDamage, or when the drawable or
+        /// rectangle changed, the whole rectangle is read and reported.
+        pub unsafe fn get_damaged_image(
+            &self,
+            stream: StreamId,
+            drawable: xlib::Drawable,
+            rect: Rect,
+            image: &mut ImageBuffer,
+        ) -> Result<Vec<Area>> {
+            let whole = vec![Area::new(0, 0, rect.w as u64, rect.h as u64)];
+            let Some(event_base) = self.damage_event_base() else {
+                self.get_image(drawable, rect)?.copy_into(image)?;
+                return Ok(whole);
+            };
+
+            let mut streams = self.streams.borrow_mut();
+            let tracked = streams.entry(stream).or_default();
+            let mut state = match tracked.damage.take() {
+                Some(state) if state.tracker.drawable() == drawable && state.rect == rect => state,
+                _ => {
+                    // Tracking starts before the first read, so no change can slip in between.
+                    let tracker = match self
+                        .with_error_trap(|| DamageTracker::new(**self, event_base, drawable))
+                    {
+                        Ok(tracker) => tracker,
+                        Err(_) => {
+                            self.get_image(drawable, rect)?.copy_into(image)?;
+                            return Ok(whole);
+                        }
+                    };
+                    let mut state = DamagedImage {
+                        tracker,
+                        rect,
+                        image: ImageBuffer::new(0, 0),
+                    };
+                    self.get_image(drawable, rect)?
+                        .copy_into(&mut state.image)?;
+                    copy_image(&state.image, image)?;
+                    tracked.damage = Some(state);
+                    return Ok(whole);
+                }
+            };
+            // On failure the state stays dropped, since the damage was consumed but not applied.
+            let mut dirty = Vec::new();
+            for damaged in state.tracker.take() {
+                let damaged = Rect {
+                    x: damaged.x as i32,
+                    y: damaged.y as i32,
+                    w: damaged.width as u32,
+                    h: damaged.height as u32,
+                };
+                let Some(part) = damaged.intersect(&rect) else {
+                    continue;
+                };
+                let (x, y) = ((part.x - rect.x) as u32, (part.y - rect.y) as u32);
+                self.get_image(drawable, part)?
+                    .copy_to(&mut state.image, x, y)?;
+                dirty.push(Area::new(x as i64, y as i64, part.w as u64, part.h as u64));
+            }
+            copy_image(&state.image, image)?;
+            tracked.damage = Some(state);
+            Ok(dirty)
+        }
+
+        /// The XDamage event base, or `None` if damage cannot be tracked on this connection.
+        unsafe fn damage_event_base(&self) -> Option<i32> {
+            if let Some(event_base) = self.damage_event_base.get() {
+                return event_base;
+            }
+            let event_base = self
+                .has_xfixes()
+                .then(|| DamageTracker::query(**self))
+                .flatten();
+            self.damage_event_base.set(Some(event_base));
+            event_base
+        }
+
+        /// Records where the cursor was drawn into the current frame of `stream` and returns
+        /// where it was drawn into the previous one.
+        pub fn replace_last_cursor(&self, stream: StreamId, cursor: Option<Area>) -> Option<Area> {
+            let mut streams = self.streams.borrow_mut();
+            std::mem::replace(&mut streams.entry(stream).or_default().last_cursor, cursor)
+        }
+
+        /// Stops tracking damage for `stream`. Must be called inside an error trap, since the
+        /// tracked drawable may be gone.
+        pub fn end_stream(&self, stream: StreamId) {
+            self.streams.borrow_mut().remove(&stream);
+        }
+
+        /// Whether the server supports XFixes, announcing our version on first use.
+        pub unsafe fn has_xfixes(&self) -> bool {
+            if let Some(supported) = self.xfixes_supported.get() {
+                return supported;
+            }
+            let supported = xfixes::XFixesQueryExtension(**self, &mut 0, &mut 0) != xlib::False;
+            if supported {
+                // The extension rejects requests until the client announced its version.
+                let (mut major, minor) = (2, 0);
+                xfixes::XFixesQueryVersion(**self, &mut major, &minor);
+            }
+            self.xfixes_supported.set(Some(supported));
+            supported
+        }
+
+        pub unsafe fn get_cursor_image(&self) -> Result<CursorImage> {
+            if !self.has_xfixes() {
+                return Err(Error::Unsupported(Capability::Cursor));
+            }
+            let cursor = xfixes::XFixesGetCursorImage(**self);
+            if cursor.is_null() {
+                return Err(Error::backend(
+                    "x11",
+                    "XFixesGetCursorImage returned no image",
+                ));
+            }
+            let (width, height) = ((*cursor).width as u32, (*cursor).height as u32);
+            let pixels = std::slice::from_raw_parts((*cursor).pixels, (width * height) as usize);
+            let image = ImageBuffer::from_fn(width, height, |x, y| {
+                // Premultiplied ARGB, stored in the low 32 bits of each `unsigned long`.
+                let argb = pixels[(y * width + x) as usize] as u32;
+                let alpha = argb >> 24;
+                let channel = |shift: u32| match alpha {
+                    0 => 0,
+                    alpha => ((((argb >> shift) & 0xFF) * 255 + alpha / 2) / alpha).min(255) as u8,
+                };
+                image::Rgba([channel(16), channel(8), channel(0), alpha as u8])
+            });
+            let result = CursorImage {
+                image,
+                x: (*cursor).x as i64,
+                y: (*cursor).y as i64,
+                hotspot_x: (*cursor).xhot as u32,
+                hotspot_y: (*cursor).yhot as u32,
+            };
+            xlib::XFree(cursor as *mut std::os::raw::c_void);
+            Ok(result)
+        }
+
+        /// Returns the desktop coordinates of the top-left corner of `rect` in `drawable`.
+        ///
+        /// Root coordinates of the default screen are desktop coordinates. The roots of other X
+        /// screens are shifted to where `get_monitors` places them, while windows are always
+        /// positioned relative to the default root.
+        pub unsafe fn get_origin(
+            &self,
+            drawable: xlib::Drawable,
+            rect: Rect,
+        ) -> Result<(i32, i32)> {
+            if drawable == self.get_root_window_of_screen() {
+                return Ok((rect.x, rect.y));
+            }
+            if self.is_root_window(drawable) {
+                let (offset_x, offset_y) = self
+                    .get_monitors()
+                    .into_iter()
+                    .find(|monitor| monitor.root == drawable)
+                    .map_or((0, 0), |monitor| {
+                        (
+                            monitor.desktop.x - monitor.rect.x,
+                            monitor.desktop.y - monitor.rect.y,
+                        )
+                    });
+                return Ok((rect.x + offset_x, rect.y + offset_y));
+            }
+            let window_rect = self.get_client_rect(drawable)?;
+            Ok((window_rect.x + rect.x, window_rect.y + rect.y))
+        }
+
         /*pub unsafe fn get_default_root(&self)-> xlib::Window {
             xlib::XDefaultRootWindow(**self)
         }*/
@@ -167,136 +605,1322 @@ mod xutils {
             xlib::XDefaultScreenOfDisplay(**self)
         }
 
+        /// Returns the size of the default screen's root window.
+        pub unsafe fn get_screen_area(&self) -> Area {
+            let screen = self.get_default_screen_of_display();
+            Area::new(0, 0, (*screen).width as u64, (*screen).height as u64)
+        }
+
         pub unsafe fn get_root_window_of_screen(&self) -> xlib::Window {
             let xdsoc = self.get_default_screen_of_display();
             xlib::XRootWindowOfScreen(xdsoc)
         }
-    }
 
-    impl TryInto<ImageBuffer> for XImageHandle {
-        type Error = Error;
+        /// Whether `window` is the root window of any X screen.
+        pub unsafe fn is_root_window(&self, window: xlib::Window) -> bool {
+            (0..xlib::XScreenCount(**self))
+                .any(|screen| xlib::XRootWindow(**self, screen) == window)
+        }
 
-        fn try_into(self) -> std::result::Result<ImageBuffer, Self::Error> {
-            #[inline(always)]
-            unsafe fn channel_offset(handle: &XImageHandle, mask: u64) -> Result<u32> {
-                match ((***handle).byte_order, mask & 0xFFFFFFFF) {
-                    (0, 0xFF) | (1, 0xFF000000) => Ok(0),
-                    (0, 0xFF00) | (1, 0xFF0000) => Ok(1),
-                    (0, 0xFF0000) | (1, 0xFF00) => Ok(2),
-                    (0, 0xFF000000) | (1, 0xFF) => Ok(3),
-                    _ => Err(Error::WindowCaptureFailed),
+        /// Returns the active XRandR monitors of all X screens, those of the default screen first
+        /// and each in the order reported by the server.
+        ///
+        /// Every X screen has its own coordinate space, so additional screens are placed to the
+        /// right of the desktop in the order of their screen number.
+        pub unsafe fn get_monitors(&self) -> Vec<Monitor> {
+            let default = xlib::XDefaultScreen(**self);
+            let mut monitors = self.get_screen_monitors(xlib::XRootWindow(**self, default), (0, 0));
+            for screen in (0..xlib::XScreenCount(**self)).filter(|&screen| screen != default) {
+                let right = monitors
+                    .iter()
+                    .map(|monitor| monitor.desktop.x + monitor.desktop.w as i32)
+                    .max()
+                    .unwrap_or(0);
+                monitors.extend(
+                    self.get_screen_monitors(xlib::XRootWindow(**self, screen), (right, 0)),
+                );
+            }
+            monitors
+        }
+
+        unsafe fn get_screen_monitors(
+            &self,
+            root: xlib::Window,
+            (offset_x, offset_y): (i32, i32),
+        ) -> Vec<Monitor> {
+            let mut count = 0;
+            let monitors = xrandr::XRRGetMonitors(**self, root, xlib::True, &mut count);
+            if monitors.is_null() {
+                return Vec::new();
+            }
+            let resources = xrandr::XRRGetScreenResourcesCurrent(**self, root);
+            let result = std::slice::from_raw_parts(monitors, count.max(0) as usize)
+                .iter()
+                .map(|m| {
+                    let (rotation, refresh_rate) = match m.noutput {
+                        n if n > 0 && !resources.is_null() => {
+                            self.get_output_mode(resources, *m.outputs)
+                        }
+                        _ => (xrandr::RR_Rotate_0 as xrandr::Rotation, None),
+                    };
+                    let rect = Rect {
+                        x: m.x,
+                        y: m.y,
+                        w: m.width as u32,
+                        h: m.height as u32,
+                    };
+                    Monitor {
+                        name: self.get_atom_name(m.name).unwrap_or_default(),
+                        root,
+                        rect,
+                        desktop: Rect {
+                            x: rect.x + offset_x,
+                            y: rect.y + offset_y,
+                            ..rect
+                        },
+                        primary: m.primary != 0,
+                        rotation,
+                        refresh_rate,
+                    }
+                })
+                .collect();
+            if !resources.is_null() {
+                xrandr::XRRFreeScreenResources(resources);
+            }
+            xrandr::XRRFreeMonitors(monitors);
+            result
+        }
+
+        /// Looks up the rotation and refresh rate of the CRTC driving `output`.
+        unsafe fn get_output_mode(
+            &self,
+            resources: *mut xrandr::XRRScreenResources,
+            output: xrandr::RROutput,
+        ) -> (xrandr::Rotation, Option<f64>) {
+            let default = (xrandr::RR_Rotate_0 as xrandr::Rotation, None);
+            let output_info = xrandr::XRRGetOutputInfo(**self, resources, output);
+            if output_info.is_null() {
+                return default;
+            }
+            let crtc = (*output_info).crtc;
+            xrandr::XRRFreeOutputInfo(output_info);
+            if crtc == 0 {
+                return default;
+            }
+            let crtc_info = xrandr::XRRGetCrtcInfo(**self, resources, crtc);
+            if crtc_info.is_null() {
+                return default;
+            }
+            let (rotation, mode) = ((*crtc_info).rotation, (*crtc_info).mode);
+            xrandr::XRRFreeCrtcInfo(crtc_info);
+            let modes =
+                std::slice::from_raw_parts((*resources).modes, (*resources).nmode.max(0) as usize);
+            let refresh_rate = modes.iter().find(|m| m.id == mode).and_then(|m| {
+                let mut v_total = m.vTotal as f64;
+                if m.modeFlags & xrandr::RR_DoubleScan as xrandr::XRRModeFlags != 0 {
+                    v_total *= 2.0;
+                }
+                if m.modeFlags & xrandr::RR_Interlace as xrandr::XRRModeFlags != 0 {
+                    v_total /= 2.0;
                 }
+                match m.hTotal as f64 * v_total {
+                    total if total > 0.0 => Some(m.dotClock as f64 / total),
+                    _ => None,
+                }
+            });
+            (rotation, refresh_rate)
+        }
+
+        pub unsafe fn intern_atom(&self, name: &str) -> xlib::Atom {
+            let name = std::ffi::CString::new(name).unwrap_or_default();
+            xlib::XInternAtom(**self, name.as_ptr(), xlib::False)
+        }
+
+        /// Reads a window property of the given type and returns its raw items.
+        ///
+        /// Items of format 32 are returned by Xlib as `c_ulong`, regardless of the platform's
+        /// word size. Callers pick `T` accordingly.
+        unsafe fn get_property<T: Copy>(
+            &self,
+            window: xlib::Window,
+            property: &str,
+            req_type: xlib::Atom,
+        ) -> Option<Vec<T>> {
+            let property = self.intern_atom(property);
+            let mut actual_type = 0;
+            let mut actual_format = 0;
+            let mut nitems = 0;
+            let mut bytes_after = 0;
+            let mut data: *mut u8 = std::ptr::null_mut();
+            let status = xlib::XGetWindowProperty(
+                **self,
+                window,
+                property,
+                0,
+                i32::MAX as std::os::raw::c_long,
+                xlib::False,
+                req_type,
+                &mut actual_type,
+                &mut actual_format,
+                &mut nitems,
+                &mut bytes_after,
+                &mut data,
+            );
+            if status != xlib::Success as i32 || data.is_null() {
+                return None;
             }
+            let items = match actual_type {
+                t if t == req_type => {
+                    Some(std::slice::from_raw_parts(data as *const T, nitems as usize).to_vec())
+                }
+                _ => None,
+            };
+            xlib::XFree(data as *mut std::os::raw::c_void);
+            items
+        }
 
-            #[inline(always)]
-            unsafe fn subpixel_at(
-                handle: &XImageHandle,
-                x: u32,
-                y: u32,
-                data: &[u8],
-                stride: u32,
-                channel_offset: u32,
-            ) -> u8 {
-                let index = y * (***handle).bytes_per_line as u32 + x * stride + channel_offset;
-                data[index as usize]
+        pub unsafe fn get_cardinal_property(
+            &self,
+            window: xlib::Window,
+            property: &str,
+        ) -> Option<Vec<std::os::raw::c_ulong>> {
+            self.get_property(window, property, xlib::XA_CARDINAL)
+        }
+
+        pub unsafe fn get_atom_property(
+            &self,
+            window: xlib::Window,
+            property: &str,
+        ) -> Option<Vec<xlib::Atom>> {
+            self.get_property(window, property, xlib::XA_ATOM)
+        }
+
+        pub unsafe fn get_utf8_property(
+            &self,
+            window: xlib::Window,
+            property: &str,
+        ) -> Option<String> {
+            let utf8_string = self.intern_atom("UTF8_STRING");
+            self.get_property::<u8>(window, property, utf8_string)
+                .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+        }
+
+        /// Returns the outermost ancestor of `window` below the root, which is the frame a
+        /// reparenting window manager draws the decorations into.
+        pub unsafe fn get_frame_window(&self, window: xlib::Window) -> xlib::Window {
+            let mut current = window;
+            loop {
+                let (mut root, mut parent) = (0, 0);
+                let mut children: *mut xlib::Window = std::ptr::null_mut();
+                let mut nchildren = 0;
+                let status = xlib::XQueryTree(
+                    **self,
+                    current,
+                    &mut root,
+                    &mut parent,
+                    &mut children,
+                    &mut nchildren,
+                );
+                if !children.is_null() {
+                    xlib::XFree(children as *mut std::os::raw::c_void);
+                }
+                if status == 0 || parent == 0 || parent == root {
+                    return current;
+                }
+                current = parent;
             }
+        }
 
-            unsafe {
-                let stride = match ((**self).depth, (**self).bits_per_pixel) {
-                    (24, 24) => Ok(3),
-                    (24, 32) | (32, 32) => Ok(4),
-                    _ => Err(Error::WindowCaptureFailed),
-                }?;
-
-                let (mask_r, mask_g, mask_b) =
-                    ((**self).red_mask, (**self).green_mask, (**self).blue_mask);
-                let mask_a = !(mask_r | mask_g | mask_b);
-                let offset_r = channel_offset(&self, mask_r)?;
-                let offset_g = channel_offset(&self, mask_g)?;
-                let offset_b = channel_offset(&self, mask_b)?;
-                let offset_a = channel_offset(&self, mask_a)?;
-                let size = ((**self).bytes_per_line * (**self).height) as usize;
-                let data = std::slice::from_raw_parts((**self).data as *const u8, size);
-
-                let image =
-                    ImageBuffer::from_fn((**self).width as u32, (**self).height as u32, |x, y| {
-                        *image::Pixel::from_slice(&[
-                            subpixel_at(&self, x, y, data, stride, offset_r),
-                            subpixel_at(&self, x, y, data, stride, offset_g),
-                            subpixel_at(&self, x, y, data, stride, offset_b),
-                            if (**self).depth == 24 {
-                                0xFF
-                            } else {
-                                subpixel_at(&self, x, y, data, stride, offset_a)
-                            },
-                        ])
-                    });
-                Ok(image)
+        /// Returns the `_NET_FRAME_EXTENTS` of `window` as left, right, top and bottom widths.
+        pub unsafe fn get_frame_extents(&self, window: xlib::Window) -> Option<[u32; 4]> {
+            match *self.get_cardinal_property(window, "_NET_FRAME_EXTENTS")? {
+                [left, right, top, bottom] => {
+                    Some([left as u32, right as u32, top as u32, bottom as u32])
+                }
+                _ => None,
+            }
+        }
+
+        /// Returns the managed top-level windows from bottom to top, as advertised by an EWMH
+        /// compliant window manager.
+        pub unsafe fn get_client_list(&self) -> Vec<xlib::Window> {
+            let root = self.get_root_window_of_screen();
+            self.get_property::<xlib::Window>(root, "_NET_CLIENT_LIST_STACKING", xlib::XA_WINDOW)
+                .or_else(|| self.get_property(root, "_NET_CLIENT_LIST", xlib::XA_WINDOW))
+                .unwrap_or_default()
+        }
+
+        pub unsafe fn get_window_title(&self, window: xlib::Window) -> String {
+            if let Some(title) = self.get_utf8_property(window, "_NET_WM_NAME") {
+                return title;
+            }
+            let mut name: *mut std::os::raw::c_char = std::ptr::null_mut();
+            if xlib::XFetchName(**self, window, &mut name) == 0 || name.is_null() {
+                return String::new();
             }
+            let title = std::ffi::CStr::from_ptr(name)
+                .to_string_lossy()
+                .into_owned();
+            xlib::XFree(name as *mut std::os::raw::c_void);
+            title
+        }
+
+        /// Returns the `(instance, class)` pair from `WM_CLASS`.
+        pub unsafe fn get_window_class(
+            &self,
+            window: xlib::Window,
+        ) -> (Option<String>, Option<String>) {
+            unsafe fn take(ptr: *mut std::os::raw::c_char) -> Option<String> {
+                if ptr.is_null() {
+                    return None;
+                }
+                let value = std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned();
+                xlib::XFree(ptr as *mut std::os::raw::c_void);
+                Some(value)
+            }
+
+            let mut hint = xlib::XClassHint {
+                res_name: std::ptr::null_mut(),
+                res_class: std::ptr::null_mut(),
+            };
+            if xlib::XGetClassHint(**self, window, &mut hint) == 0 {
+                return (None, None);
+            }
+            (take(hint.res_name), take(hint.res_class))
+        }
+
+        pub unsafe fn get_map_state(&self, window: xlib::Window) -> i32 {
+            let mut attrs = std::mem::MaybeUninit::<xlib::XWindowAttributes>::zeroed();
+            match xlib::XGetWindowAttributes(**self, window, attrs.as_mut_ptr()) {
+                0 => xlib::IsUnmapped,
+                _ => attrs.assume_init().map_state,
+            }
+        }
+
+        pub unsafe fn get_atom_name(&self, atom: xlib::Atom) -> Option<String> {
+            let name = xlib::XGetAtomName(**self, atom);
+            if name.is_null() {
+                return None;
+            }
+            let result = std::ffi::CStr::from_ptr(name)
+                .to_string_lossy()
+                .into_owned();
+            xlib::XFree(name as *mut std::os::raw::c_void);
+            Some(result)
+        }
+
+        /// Returns the `Xft.dpi` resource relative to the X11 default of 96 DPI.
+        pub unsafe fn get_scale_factor(&self) -> f64 {
+            let resources = xlib::XResourceManagerString(**self);
+            if resources.is_null() {
+                return 1.0;
+            }
+            std::ffi::CStr::from_ptr(resources)
+                .to_string_lossy()
+                .lines()
+                .find_map(|line| {
+                    let (key, value) = line.split_once(':')?;
+                    match key.trim() {
+                        "Xft.dpi" => value.trim().parse::<f64>().ok(),
+                        _ => None,
+                    }
+                })
+                .map_or(1.0, |dpi| dpi / 96.0)
+        }
+
+        pub unsafe fn get_pointer_position(&self) -> (i32, i32) {
+            let (x, y, _) = self.query_pointer();
+            (x, y)
+        }
+
+        /// Returns the top-level window below the pointer, or 0 if it is over the root window.
+        pub unsafe fn get_pointer_child(&self) -> xlib::Window {
+            self.query_pointer().2
+        }
+
+        unsafe fn query_pointer(&self) -> (i32, i32, xlib::Window) {
+            let root = self.get_root_window_of_screen();
+            let (mut root_ret, mut child_ret) = (0, 0);
+            let (mut root_x, mut root_y, mut win_x, mut win_y) = (0, 0, 0, 0);
+            let mut mask = 0;
+            xlib::XQueryPointer(
+                **self,
+                root,
+                &mut root_ret,
+                &mut child_ret,
+                &mut root_x,
+                &mut root_y,
+                &mut win_x,
+                &mut win_y,
+                &mut mask,
+            );
+            (root_x, root_y, child_ret)
+        }
+    }
+
+    /// Bounding box of `rects`.
+    pub fn bounding_rect(rects: impl IntoIterator<Item = Rect>) -> Option<Rect> {
+        rects.into_iter().reduce(|a, b| {
+            let x = a.x.min(b.x);
+            let y = a.y.min(b.y);
+            let right = (a.x as i64 + a.w as i64).max(b.x as i64 + b.w as i64);
+            let bottom = (a.y as i64 + a.h as i64).max(b.y as i64 + b.h as i64);
+            Rect {
+                x,
+                y,
+                w: (right - x as i64) as u32,
+                h: (bottom - y as i64) as u32,
+            }
+        })
+    }
+
+    /// The spans of row `y` in `0..width` outside of all `rects`.
+    fn row_gaps(rects: &[Rect], y: i64, width: i64) -> Vec<(i64, i64)> {
+        let mut spans: Vec<(i64, i64)> = rects
+            .iter()
+            .filter(|rect| (rect.y as i64..rect.y as i64 + rect.h as i64).contains(&y))
+            .map(|rect| (rect.x as i64, rect.x as i64 + rect.w as i64))
+            .collect();
+        spans.sort_unstable();
+        let mut gaps = Vec::new();
+        let mut x = 0;
+        for (start, end) in spans.into_iter().chain([(width, width)]) {
+            if x < start.min(width) {
+                gaps.push((x, start.min(width)));
+            }
+            x = x.max(end);
+        }
+        gaps
+    }
+
+    /// Fills the pixels of `image` outside of all `rects`, given in image coordinates.
+    pub fn fill_uncovered(image: &mut ImageBuffer, rects: &[Rect], color: image::Rgba<u8>) {
+        for y in 0..image.height() {
+            for (start, end) in row_gaps(rects, y as i64, image.width() as i64) {
+                for x in start..end {
+                    image.put_pixel(x as u32, y, color);
+                }
+            }
+        }
+    }
+
+    /// Whether `rects` leave any pixel of a `width` x `height` image uncovered.
+    pub fn has_gaps(rects: &[Rect], width: u32, height: u32) -> bool {
+        // Coverage only changes at the top and bottom edges of the rects.
+        let edges = rects
+            .iter()
+            .flat_map(|rect| [rect.y as i64, rect.y as i64 + rect.h as i64]);
+        std::iter::once(0)
+            .chain(edges)
+            .filter(|y| (0..height as i64).contains(y))
+            .any(|y| !row_gaps(rects, y, width as i64).is_empty())
+    }
+
+    /// Copies `source` into `target`, reusing its allocation if the size matches.
+    fn copy_image(source: &ImageBuffer, target: &mut ImageBuffer) -> Result<()> {
+        resize(target, source.width(), source.height())?;
+        target.copy_from_slice(source);
+        Ok(())
+    }
+
+    pub fn resize(image: &mut ImageBuffer, width: u32, height: u32) -> Result<()> {
+        if image.dimensions() != (width, height) {
+            let mut raw = std::mem::take(image).into_raw();
+            raw.resize(width as usize * height as usize * 4, 0);
+            *image = ImageBuffer::from_raw(width, height, raw)
+                .ok_or(Error::InvalidImageData { width, height })?;
+        }
+        Ok(())
+    }
+
+    impl XImageHandle {
+        /// Converts the image into `image`, reusing its allocation if the size matches.
+        pub unsafe fn copy_into(&self, image: &mut ImageBuffer) -> Result<()> {
+            resize(image, (***self).width as u32, (***self).height as u32)?;
+            self.copy_to(image, 0, 0)
+        }
+
+        /// Converts the image into the rectangle of `image` starting at (`x`, `y`).
+        pub unsafe fn copy_to(&self, image: &mut ImageBuffer, x: u32, y: u32) -> Result<()> {
+            self.raw_frame()?.copy_to(image, x, y)
+        }
+
+        /// Borrows the pixels in the layout the server sent them.
+        pub unsafe fn raw_frame(&self) -> Result<RawFrame<'_>> {
+            let unsupported_format = || Error::UnsupportedPixelFormat {
+                depth: (***self).depth as u32,
+                bpp: (***self).bits_per_pixel as u32,
+                masks: [
+                    (***self).red_mask,
+                    (***self).green_mask,
+                    (***self).blue_mask,
+                ],
+            };
+
+            let bytes_per_pixel = match ((***self).depth, (***self).bits_per_pixel) {
+                (24, 24) => 3,
+                (24, 32) | (32, 32) => 4,
+                _ => return Err(unsupported_format()),
+            };
+            // Masks describe the pixel as a number, so the byte order decides where a channel is.
+            let channel_offset = |mask: u64| {
+                let shift = mask.trailing_zeros();
+                let byte = (shift / 8) as u8;
+                if shift % 8 != 0 || byte >= bytes_per_pixel || mask >> shift != 0xFF {
+                    return Err(unsupported_format());
+                }
+                Ok(match (***self).byte_order {
+                    xlib::LSBFirst => byte,
+                    _ => bytes_per_pixel - 1 - byte,
+                })
+            };
+
+            let (mask_r, mask_g, mask_b) = (
+                (***self).red_mask,
+                (***self).green_mask,
+                (***self).blue_mask,
+            );
+            let alpha = match (***self).depth {
+                32 => Some(channel_offset(!(mask_r | mask_g | mask_b) & 0xFFFFFFFF)?),
+                _ => None,
+            };
+            let format = PixelFormat::new(
+                bytes_per_pixel,
+                channel_offset(mask_r)?,
+                channel_offset(mask_g)?,
+                channel_offset(mask_b)?,
+                alpha,
+            );
+            let stride = (***self).bytes_per_line as usize;
+            let (width, height) = ((***self).width as u32, (***self).height as u32);
+            let data =
+                std::slice::from_raw_parts((***self).data as *const u8, stride * height as usize);
+            RawFrame::new(width, height, stride, format, data)
+        }
+    }
+
+    impl TryInto<ImageBuffer> for XImageHandle {
+        type Error = Error;
+
+        fn try_into(self) -> std::result::Result<ImageBuffer, Self::Error> {
+            let mut image = ImageBuffer::new(0, 0);
+            unsafe { self.copy_into(&mut image)? };
+            Ok(image)
         }
     }
 }
 
+/// X11 capture backend.
+///
+/// The provider owns a single connection to the X server, which is opened on first use and
+/// reopened after the server connection was lost. Calls are serialized on that connection, so a
+/// provider can be shared between threads.
+///
+/// Windows are read through XComposite where the server supports it, so overlapping windows and
+/// the screen edges do not affect window captures. The first capture of a window waits up to
+/// 100 ms for it to repaint after being redirected, without blocking other calls on the
+/// provider; it stays redirected until the connection is closed.
 #[derive(Default)]
-pub struct X11Provider;
+pub struct X11Provider {
+    connection: Mutex<Option<xutils::XDisplayHandle>>,
+    display_name: Option<String>,
+    xauthority: Option<PathBuf>,
+    cursor: bool,
+    decorations: bool,
+    without_composite: bool,
+    background: Option<image::Rgba<u8>>,
+}
 
 impl Provider for X11Provider {
     fn new() -> Self {
-        Self
+        Self::default()
     }
 }
 
-impl WindowCaptureProvider for X11Provider {
-    fn capture_window(&self, window_id: WindowId) -> Result<ImageBuffer> {
-        unsafe {
-            let display = xutils::XDisplayHandle::open_default_display()?;
-            let window_rect = display.get_client_rect(window_id);
-            let client_rect = window_rect.to_client_coordinates();
-            let ximage = display.get_image(window_id, client_rect)?;
-            let image: ImageBuffer = ximage.try_into()?;
-            Ok(image)
+impl X11Provider {
+    /// Creates a provider for the named display (e.g. `:99`) instead of `$DISPLAY`.
+    pub fn with_display(name: impl Into<String>) -> Self {
+        Self {
+            display_name: Some(name.into()),
+            ..Self::default()
         }
     }
 
-    fn capture_focused_window(&self) -> Result<ImageBuffer> {
+    /// Authenticates with the given Xauthority file instead of `$XAUTHORITY`.
+    ///
+    /// The cookie is looked up by display name, so connecting fails with
+    /// [`Error::DisplayUnavailable`] if neither [`with_display`](Self::with_display) nor
+    /// `$DISPLAY` names one.
+    pub fn with_xauthority(mut self, path: impl Into<PathBuf>) -> Self {
+        self.xauthority = Some(path.into());
+        self
+    }
+
+    /// Reads windows from the screen instead of through XComposite, which keeps them from being
+    /// redirected but includes overlapping windows in the capture.
+    pub fn without_composite(mut self) -> Self {
+        self.without_composite = true;
+        self
+    }
+
+    /// Colour for the parts of full captures that no monitor shows. Defaults to opaque black.
+    pub fn with_background(mut self, background: image::Rgba<u8>) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Includes the title bar and borders drawn by the window manager in window captures.
+    pub fn with_decorations(mut self, decorations: bool) -> Self {
+        self.decorations = decorations;
+        self
+    }
+
+    /// Draws the mouse pointer into every capture. Ignored if the server lacks XFixes.
+    pub fn with_cursor(mut self, cursor: bool) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    unsafe fn open_display(&self) -> Result<xutils::XDisplayHandle> {
+        let name = match &self.display_name {
+            Some(name) => {
+                Some(
+                    CString::new(name.as_str()).map_err(|_| Error::DisplayUnavailable {
+                        name: Some(name.clone()),
+                    })?,
+                )
+            }
+            None => None,
+        };
+        let display_name = self
+            .display_name
+            .clone()
+            .or_else(|| std::env::var("DISPLAY").ok());
+        match (&self.xauthority, display_name) {
+            (Some(path), Some(display_name)) => {
+                let cookie = xauth::Cookie::load(path, &display_name)?;
+                xutils::XDisplayHandle::open(name, Some(&cookie))
+            }
+            // Opening without the cookie would silently drop the caller's Xauthority file.
+            (Some(_), None) => Err(Error::DisplayUnavailable { name: None }),
+            (None, _) => xutils::XDisplayHandle::open(name, None),
+        }
+    }
+
+    /// Runs `f` on the provider's display connection with X protocol errors trapped.
+    fn with_connection<T>(
+        &self,
+        f: impl FnOnce(&xutils::XDisplayHandle) -> Result<T>,
+    ) -> Result<T> {
+        let mut connection = self.connection.lock().unwrap_or_else(|e| e.into_inner());
         unsafe {
-            let window = xutils::XDisplayHandle::open_default_display()?.get_focused_window();
-            self.capture_window(window)
+            let display = match connection.take() {
+                Some(display) if !display.is_broken() => display,
+                _ => self.open_display()?,
+            };
+            display.set_composite(!self.without_composite);
+            let result = display.with_error_trap(|| f(&display));
+            if display.is_broken() {
+                return Err(Error::ConnectionLost);
+            }
+            *connection = Some(display);
+            result
+        }
+    }
+
+    /// Runs `f` like [`with_connection`](Self::with_connection), but if `f` stopped at a window
+    /// that was just redirected, releases the connection until the window was repainted and
+    /// runs `f` again.
+    fn with_repainted_windows<T>(
+        &self,
+        mut f: impl FnMut(&xutils::XDisplayHandle) -> Result<T>,
+    ) -> Result<T> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(5);
+        loop {
+            let mut deadline = None;
+            let result = self.with_connection(|display| {
+                let result = f(display);
+                deadline = display.take_repaint_deadline();
+                result
+            });
+            match (deadline, &result) {
+                (Some(deadline), Err(Error::Backend { .. })) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    std::thread::sleep(POLL_INTERVAL.min(remaining));
+                }
+                _ => return result,
+            }
+        }
+    }
+
+    /// Resolves a window to its drawable and the rectangle to capture.
+    unsafe fn window_rect(
+        display: &xutils::XDisplayHandle,
+        window_id: WindowId,
+        options: &CaptureOptions,
+    ) -> Result<(x11::xlib::Drawable, xutils::Rect)> {
+        let window_rect = display.get_client_rect(window_id)?;
+        if display.get_map_state(window_id) != x11::xlib::IsViewable {
+            return Err(Error::WindowNotViewable(window_id));
+        }
+        if options.decorations {
+            let frame = display.get_frame_window(window_id);
+            if frame != window_id {
+                let frame_rect = display.get_client_rect(frame)?;
+                return Ok((frame, frame_rect.to_client_coordinates()));
+            }
+            // Without a frame window, the decorations are around the window on the root window.
+            if let Some([left, right, top, bottom]) = display.get_frame_extents(window_id) {
+                let rect = xutils::Rect {
+                    x: window_rect.x - left as i32,
+                    y: window_rect.y - top as i32,
+                    w: window_rect.w + left + right,
+                    h: window_rect.h + top + bottom,
+                };
+                let screen: xutils::Rect =
+                    GenericArea::<i32, u32>::try_from(display.get_screen_area())?.into();
+                let rect = rect
+                    .intersect(&screen)
+                    .ok_or(Error::WindowNotViewable(window_id))?;
+                return Ok((display.get_root_window_of_screen(), rect));
+            }
+        }
+        Ok((window_id, window_rect.to_client_coordinates()))
+    }
+
+    unsafe fn area_rect(
+        display: &xutils::XDisplayHandle,
+        area: Area,
+    ) -> Result<(x11::xlib::Drawable, xutils::Rect)> {
+        let bounds = display.get_screen_area();
+        let fits = area.x >= 0
+            && area.y >= 0
+            && (area.x as u64).saturating_add(area.width) <= bounds.width
+            && (area.y as u64).saturating_add(area.height) <= bounds.height;
+        if !fits || area.width == 0 || area.height == 0 {
+            return Err(Error::AreaOutOfBounds { area, bounds });
+        }
+        let rect: xutils::Rect = GenericArea::<i32, u32>::try_from(area)?.into();
+        Ok((display.get_root_window_of_screen(), rect))
+    }
+
+    unsafe fn screen_rect(
+        display: &xutils::XDisplayHandle,
+        screen_id: ScreenId,
+    ) -> Result<(x11::xlib::Drawable, xutils::Rect)> {
+        let monitor = usize::try_from(screen_id)
+            .ok()
+            .and_then(|index| display.get_monitors().into_iter().nth(index))
+            .ok_or(Error::ScreenNotFound(screen_id))?;
+        Ok((monitor.root, monitor.rect))
+    }
+
+    unsafe fn focused_window(display: &xutils::XDisplayHandle) -> Result<WindowId> {
+        match display.get_focused_window() {
+            window if window == x11::xlib::PointerRoot as u64 || window == 0 => {
+                Err(Error::NoFocusedWindow)
+            }
+            window => Ok(window),
         }
     }
+
+    /// The current screen is the monitor that contains the mouse pointer.
+    unsafe fn current_screen(display: &xutils::XDisplayHandle) -> ScreenId {
+        let (x, y) = display.get_pointer_position();
+        let root = display.get_root_window_of_screen();
+        display
+            .get_monitors()
+            .iter()
+            .position(|monitor| monitor.root == root && monitor.rect.contains(x, y))
+            .unwrap_or(0) as ScreenId
+    }
+
+    /// The managed window whose top-level window is below the pointer.
+    unsafe fn window_under_cursor(display: &xutils::XDisplayHandle) -> Result<WindowId> {
+        let child = display.get_pointer_child();
+        if child == 0 {
+            return Err(Error::NoWindowUnderCursor);
+        }
+        display
+            .get_client_list()
+            .into_iter()
+            .rev()
+            .find(|&window| {
+                window == child
+                    || display
+                        .with_error_trap(|| Ok(display.get_frame_window(window) == child))
+                        .unwrap_or(false)
+            })
+            .ok_or(Error::NoWindowUnderCursor)
+    }
+
+    /// Resolves targets that depend on the pointer or focus to a window or screen.
+    unsafe fn resolve(
+        display: &xutils::XDisplayHandle,
+        target: &CaptureTarget,
+    ) -> Result<CaptureTarget> {
+        match *target {
+            CaptureTarget::FocusedWindow => {
+                Self::focused_window(display).map(CaptureTarget::Window)
+            }
+            CaptureTarget::CurrentScreen => {
+                Ok(CaptureTarget::Screen(Self::current_screen(display)))
+            }
+            CaptureTarget::WindowUnderCursor => {
+                Self::window_under_cursor(display).map(CaptureTarget::Window)
+            }
+            target => Ok(target),
+        }
+    }
+
+    /// Resolves a target to the drawable and rectangle that currently show it.
+    unsafe fn target_rect(
+        display: &xutils::XDisplayHandle,
+        target: &CaptureTarget,
+        options: &CaptureOptions,
+    ) -> Result<(x11::xlib::Drawable, xutils::Rect)> {
+        match *target {
+            CaptureTarget::Window(window_id) => Self::window_rect(display, window_id, options),
+            CaptureTarget::FocusedWindow => {
+                Self::window_rect(display, Self::focused_window(display)?, options)
+            }
+            CaptureTarget::WindowUnderCursor => {
+                Self::window_rect(display, Self::window_under_cursor(display)?, options)
+            }
+            CaptureTarget::Screen(screen_id) => Self::screen_rect(display, screen_id),
+            CaptureTarget::CurrentScreen => {
+                Self::screen_rect(display, Self::current_screen(display))
+            }
+            CaptureTarget::Area(area) => Self::area_rect(display, area),
+            // The monitors of the default X screen; see `desktop_image` for the others.
+            CaptureTarget::Full => {
+                let root = display.get_root_window_of_screen();
+                let monitors = display.get_monitors();
+                let on_root = monitors.iter().filter(|monitor| monitor.root == root);
+                match xutils::bounding_rect(on_root.map(|monitor| monitor.rect)) {
+                    Some(rect) => Ok((root, rect)),
+                    None => Self::area_rect(display, display.get_screen_area()),
+                }
+            }
+        }
+    }
+
+    /// Whether full captures have to be stitched together from several X screens.
+    unsafe fn spans_x_screens(display: &xutils::XDisplayHandle) -> bool {
+        let root = display.get_root_window_of_screen();
+        display
+            .get_monitors()
+            .iter()
+            .any(|monitor| monitor.root != root)
+    }
+
+    fn background(&self) -> image::Rgba<u8> {
+        self.background.unwrap_or(image::Rgba([0, 0, 0, 255]))
+    }
+
+    /// The monitors of the default root, relative to a full capture of `rect`.
+    unsafe fn covered_rects(
+        display: &xutils::XDisplayHandle,
+        rect: xutils::Rect,
+    ) -> Vec<xutils::Rect> {
+        let root = display.get_root_window_of_screen();
+        display
+            .get_monitors()
+            .into_iter()
+            .filter(|monitor| monitor.root == root)
+            .map(|monitor| xutils::Rect {
+                x: monitor.rect.x - rect.x,
+                y: monitor.rect.y - rect.y,
+                ..monitor.rect
+            })
+            .collect()
+    }
+
+    /// Fills the parts of a full capture of `rect` on the default root that no monitor shows.
+    unsafe fn fill_gaps(
+        &self,
+        display: &xutils::XDisplayHandle,
+        rect: xutils::Rect,
+        image: &mut ImageBuffer,
+    ) {
+        let covered = Self::covered_rects(display, rect);
+        if !covered.is_empty() {
+            xutils::fill_uncovered(image, &covered, self.background());
+        }
+    }
+
+    /// Captures all monitors of all X screens into one image laid out as on the desktop, and
+    /// returns the desktop area it covers. Pixels outside of all monitors get the background.
+    unsafe fn desktop_image(
+        &self,
+        display: &xutils::XDisplayHandle,
+        image: &mut ImageBuffer,
+    ) -> Result<xutils::Rect> {
+        let monitors = display.get_monitors();
+        let Some(bounds) = xutils::bounding_rect(monitors.iter().map(|monitor| monitor.desktop))
+        else {
+            let (drawable, rect) = Self::area_rect(display, display.get_screen_area())?;
+            display.get_image(drawable, rect)?.copy_into(image)?;
+            return Ok(rect);
+        };
+        xutils::resize(image, bounds.w, bounds.h)?;
+        let mut roots: Vec<_> = monitors.iter().map(|monitor| monitor.root).collect();
+        roots.dedup();
+        for root in roots {
+            let on_root: Vec<_> = monitors.iter().filter(|m| m.root == root).collect();
+            let (Some(rect), Some(desktop)) = (
+                xutils::bounding_rect(on_root.iter().map(|monitor| monitor.rect)),
+                xutils::bounding_rect(on_root.iter().map(|monitor| monitor.desktop)),
+            ) else {
+                continue;
+            };
+            display.get_image(root, rect)?.copy_to(
+                image,
+                (desktop.x - bounds.x) as u32,
+                (desktop.y - bounds.y) as u32,
+            )?;
+        }
+        let covered: Vec<xutils::Rect> = monitors
+            .iter()
+            .map(|monitor| xutils::Rect {
+                x: monitor.desktop.x - bounds.x,
+                y: monitor.desktop.y - bounds.y,
+                ..monitor.desktop
+            })
+            .collect();
+        xutils::fill_uncovered(image, &covered, self.background());
+        Ok(bounds)
+    }
+
+    /// Captures `target` into `image`, returning the captured area in desktop coordinates.
+    unsafe fn capture_area_into(
+        &self,
+        display: &xutils::XDisplayHandle,
+        target: &CaptureTarget,
+        options: &CaptureOptions,
+        image: &mut ImageBuffer,
+    ) -> Result<Area> {
+        let rect = if *target == CaptureTarget::Full {
+            self.full_image(display, options, image)?
+        } else {
+            let (drawable, rect) = Self::target_rect(display, target, options)?;
+            display.get_image(drawable, rect)?.copy_into(image)?;
+            Self::draw_cursor(display, drawable, rect, options, image)?;
+            let (x, y) = display.get_origin(drawable, rect)?;
+            xutils::Rect { x, y, ..rect }
+        };
+        Ok(Area::new(
+            rect.x as i64,
+            rect.y as i64,
+            rect.w as u64,
+            rect.h as u64,
+        ))
+    }
+
+    /// Captures the desktop with the pointer drawn if enabled, returning the covered area.
+    unsafe fn full_image(
+        &self,
+        display: &xutils::XDisplayHandle,
+        options: &CaptureOptions,
+        image: &mut ImageBuffer,
+    ) -> Result<xutils::Rect> {
+        let bounds = self.desktop_image(display, image)?;
+        // The default screen's root coordinates are desktop coordinates.
+        let root = display.get_root_window_of_screen();
+        Self::draw_cursor(display, root, bounds, options, image)?;
+        Ok(bounds)
+    }
+
+    /// Draws the pointer onto a capture of `rect` in `drawable` if enabled, returning the area it
+    /// covers in image coordinates.
+    unsafe fn draw_cursor(
+        display: &xutils::XDisplayHandle,
+        drawable: x11::xlib::Drawable,
+        rect: xutils::Rect,
+        options: &CaptureOptions,
+        image: &mut ImageBuffer,
+    ) -> Result<Option<Area>> {
+        if !options.cursor {
+            return Ok(None);
+        }
+        let cursor = match display.get_cursor_image() {
+            Err(Error::Unsupported(_)) => return Ok(None),
+            cursor => cursor?,
+        };
+        let (x, y) = display.get_origin(drawable, rect)?;
+        cursor.draw_onto(image, x as i64, y as i64);
+        let area = cursor.area();
+        Ok(Some(Area::new(
+            area.x - x as i64,
+            area.y - y as i64,
+            area.width,
+            area.height,
+        )))
+    }
+
+    /// The options this provider was configured with, extended by `extra`.
+    fn options(&self, extra: &CaptureOptions) -> CaptureOptions {
+        CaptureOptions {
+            cursor: self.cursor || extra.cursor,
+            decorations: self.decorations || extra.decorations,
+        }
+    }
+
+    fn capture_target(&self, target: CaptureTarget) -> Result<ImageBuffer> {
+        let mut image = ImageBuffer::new(0, 0);
+        self.capture_into(&target, &mut image)?;
+        Ok(image)
+    }
 }
 
+impl WindowCaptureProvider for X11Provider {
+    fn capture_window(&self, window_id: WindowId) -> Result<ImageBuffer> {
+        self.capture_target(CaptureTarget::Window(window_id))
+    }
+
+    fn capture_focused_window(&self) -> Result<ImageBuffer> {
+        self.capture_target(CaptureTarget::FocusedWindow)
+    }
+}
+
+/// Screen ids map to the index of an XRandR monitor as reported by `XRRGetMonitors`.
+///
+/// Monitors of X screens other than the default one follow those of the default screen. As every
+/// X screen has its own coordinate space, they are placed to the right of the desktop, and their
+/// captures are read from their screen's root window and positioned there in the metadata.
 impl ScreenCaptureProvider for X11Provider {
-    fn capture_screen(&self, _screen_id: ScreenId) -> Result<ImageBuffer> {
-        unimplemented!()
+    fn capture_screen(&self, screen_id: ScreenId) -> Result<ImageBuffer> {
+        self.capture_target(CaptureTarget::Screen(screen_id))
     }
 
+    /// Captures the monitor that currently contains the mouse pointer.
     fn capture_current_screen(&self) -> Result<ImageBuffer> {
-        unimplemented!()
+        self.capture_target(CaptureTarget::CurrentScreen)
+    }
+}
+
+impl ScreenEnumerationProvider for X11Provider {
+    fn list_screens(&self) -> Result<Vec<Screen>> {
+        self.with_connection(|display| unsafe { Ok(Self::screens(display)) })
+    }
+}
+
+impl X11Provider {
+    /// The XRandR monitors, with their index as id.
+    unsafe fn screens(display: &xutils::XDisplayHandle) -> Vec<Screen> {
+        let scale_factor = display.get_scale_factor();
+        display
+            .get_monitors()
+            .into_iter()
+            .enumerate()
+            .map(|(index, monitor)| Screen {
+                id: index as ScreenId,
+                name: monitor.name,
+                area: Area::new(
+                    monitor.desktop.x as i64,
+                    monitor.desktop.y as i64,
+                    monitor.desktop.w as u64,
+                    monitor.desktop.h as u64,
+                ),
+                rotation: match monitor.rotation as i32 & 0xF {
+                    x11::xrandr::RR_Rotate_90 => Rotation::Left,
+                    x11::xrandr::RR_Rotate_180 => Rotation::Inverted,
+                    x11::xrandr::RR_Rotate_270 => Rotation::Right,
+                    _ => Rotation::Normal,
+                },
+                primary: monitor.primary,
+                refresh_rate: monitor.refresh_rate,
+                scale_factor,
+            })
+            .collect()
+    }
+
+    unsafe fn window_info(
+        display: &xutils::XDisplayHandle,
+        window: WindowId,
+    ) -> Result<WindowInfo> {
+        let rect = display.get_client_rect(window)?;
+        let (instance, class) = display.get_window_class(window);
+        let map_state = display.get_map_state(window);
+        let hidden = display.intern_atom("_NET_WM_STATE_HIDDEN");
+        let minimized = display
+            .get_atom_property(window, "_NET_WM_STATE")
+            .is_some_and(|state| state.contains(&hidden));
+        let cardinal = |property| {
+            display
+                .get_cardinal_property(window, property)
+                .and_then(|values| values.first().map(|&value| value as u32))
+        };
+        Ok(WindowInfo {
+            id: window as WindowId,
+            title: display.get_window_title(window),
+            class,
+            instance,
+            pid: cardinal("_NET_WM_PID"),
+            area: Area::new(rect.x as i64, rect.y as i64, rect.w as u64, rect.h as u64),
+            desktop: cardinal("_NET_WM_DESKTOP"),
+            mapped: map_state != x11::xlib::IsUnmapped,
+            visible: map_state == x11::xlib::IsViewable && !minimized,
+        })
+    }
+}
+
+impl WindowEnumerationProvider for X11Provider {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>> {
+        self.with_connection(|display| unsafe {
+            let windows = display
+                .get_client_list()
+                .into_iter()
+                // Windows may be destroyed while we walk the list, so they are skipped.
+                .filter_map(|window| {
+                    display
+                        .with_error_trap(|| Self::window_info(display, window))
+                        .ok()
+                })
+                .collect();
+            Ok(windows)
+        })
     }
 }
 
 impl AreaCaptureProvider for X11Provider {
     fn capture_area(&self, area: Area) -> Result<ImageBuffer> {
-        unsafe {
-            let area: xutils::Rect = GenericArea::<i32, u32>::try_from(area)?.into();
-            let display = xutils::XDisplayHandle::open_default_display()?;
-            let root = display.get_root_window_of_screen();
-            let ximage = display.get_image(root, area)?;
-            let image: ImageBuffer = ximage.try_into()?;
-            Ok(image)
-        }
+        self.capture_target(CaptureTarget::Area(area))
     }
 }
 
 impl FullCaptureProvider for X11Provider {
     fn capture_full(&self) -> Result<ImageBuffer> {
-        unsafe {
-            let display = xutils::XDisplayHandle::open_default_display()?;
-            let screen = display.get_default_screen_of_display();
-            let width = (*screen).width as u64;
-            let height = (*screen).height as u64;
-            self.capture_area(Area::new(0, 0, width, height))
+        self.capture_target(CaptureTarget::Full)
+    }
+}
+
+impl CaptureBackend for X11Provider {
+    fn name(&self) -> &'static str {
+        "x11"
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            window_capture: true,
+            screen_capture: true,
+            area_capture: true,
+            full_capture: true,
+            window_enumeration: true,
+            screen_enumeration: true,
+            cursor: true,
+            decorations: true,
+            alpha: false,
+            streaming: true,
+            multi_monitor: true,
+        }
+    }
+
+    fn connect(&self) -> Result<()> {
+        self.with_connection(|_| Ok(()))
+    }
+
+    fn resolve_target(&self, target: &CaptureTarget) -> Result<CaptureTarget> {
+        self.with_connection(|display| unsafe { Self::resolve(display, target) })
+    }
+
+    fn capture_with_metadata(&self, target: &CaptureTarget) -> Result<Screenshot> {
+        self.capture_with_options(target, &CaptureOptions::default())
+    }
+
+    /// Takes the position from the captured drawable, so it is exact for decorated windows too.
+    fn capture_with_options(
+        &self,
+        target: &CaptureTarget,
+        options: &CaptureOptions,
+    ) -> Result<Screenshot> {
+        let options = self.options(options);
+        let mut image = ImageBuffer::new(0, 0);
+        // Resolved, described and captured without releasing the connection in between.
+        let metadata = self.with_repainted_windows(|display| unsafe {
+            let target = Self::resolve(display, target)?;
+            let screens = Self::screens(display);
+            let window = match target {
+                CaptureTarget::Window(window_id) => display
+                    .with_error_trap(|| Self::window_info(display, window_id))
+                    .ok(),
+                _ => None,
+            };
+            let timestamp = SystemTime::now();
+            let area = self.capture_area_into(display, &target, &options, &mut image)?;
+            Ok(CaptureMetadata::new(
+                target.kind(),
+                area,
+                window,
+                &screens,
+                self.name(),
+                timestamp,
+            ))
+        })?;
+        Ok(Screenshot { image, metadata })
+    }
+
+    /// Converts straight into `buffer`, so streams do not allocate a new image per frame.
+    fn capture_into(&self, target: &CaptureTarget, buffer: &mut ImageBuffer) -> Result<()> {
+        self.with_repainted_windows(|display| unsafe {
+            let options = self.options(&CaptureOptions::default());
+            self.capture_area_into(display, target, &options, buffer)
+                .map(|_| ())
+        })
+    }
+
+    fn capture_raw(&self, target: &CaptureTarget) -> Result<RawFrame<'static>> {
+        let mut frame = None;
+        self.capture_raw_with(target, &mut |raw| frame = Some(raw.into_owned()))?;
+        Ok(frame.expect("capture_raw_with passes a frame on success"))
+    }
+
+    /// Lends the XImage or shared memory segment the server wrote to, usually as BGRX. Full
+    /// captures spanning several X screens or with gaps between the monitors are stitched and
+    /// filled with the background like [`capture_full`](FullCaptureProvider::capture_full), and
+    /// so are passed as RGBA.
+    fn capture_raw_with(
+        &self,
+        target: &CaptureTarget,
+        f: &mut dyn FnMut(RawFrame<'_>),
+    ) -> Result<()> {
+        let options = CaptureOptions {
+            cursor: false,
+            ..self.options(&CaptureOptions::default())
+        };
+        self.with_repainted_windows(|display| unsafe {
+            let (drawable, rect) = Self::target_rect(display, target, &options)?;
+            if *target == CaptureTarget::Full
+                && (Self::spans_x_screens(display)
+                    || xutils::has_gaps(&Self::covered_rects(display, rect), rect.w, rect.h))
+            {
+                let mut image = ImageBuffer::new(0, 0);
+                self.full_image(display, &options, &mut image)?;
+                f(RawFrame::from(image));
+                return Ok(());
+            }
+            let image = display.get_image(drawable, rect)?;
+            f(image.raw_frame()?);
+            Ok(())
+        })
+    }
+
+    fn capture_cursor(&self) -> Result<CursorImage> {
+        self.with_connection(|display| unsafe { display.get_cursor_image() })
+    }
+
+    fn capture_desktop(&self) -> Result<DesktopCapture> {
+        let mut image = ImageBuffer::new(0, 0);
+        let options = self.options(&CaptureOptions::default());
+        // Captured under one lock, so the image, bounds and layout describe the same state.
+        let (bounds, screens) = self.with_connection(|display| unsafe {
+            let bounds = self.full_image(display, &options, &mut image)?;
+            Ok((bounds, Self::screens(display)))
+        })?;
+        Ok(DesktopCapture {
+            image,
+            bounds: Area::new(
+                bounds.x as i64,
+                bounds.y as i64,
+                bounds.w as u64,
+                bounds.h as u64,
+            ),
+            screens,
+        })
+    }
+
+    /// Tracks the target with XDamage, so unchanged parts are neither read nor converted again.
+    fn capture_changes(
+        &self,
+        stream: StreamId,
+        target: &CaptureTarget,
+        buffer: &mut ImageBuffer,
+    ) -> Result<Vec<Area>> {
+        let options = self.options(&CaptureOptions::default());
+        self.with_repainted_windows(|display| unsafe {
+            if *target == CaptureTarget::Full && Self::spans_x_screens(display) {
+                let bounds = self.full_image(display, &options, buffer)?;
+                display.replace_last_cursor(stream, None);
+                return Ok(vec![Area::new(0, 0, bounds.w as u64, bounds.h as u64)]);
+            }
+            let (drawable, rect) = Self::target_rect(display, target, &options)?;
+            let mut dirty = display.get_damaged_image(stream, drawable, rect, buffer)?;
+            if *target == CaptureTarget::Full {
+                self.fill_gaps(display, rect, buffer);
+            }
+            // The pointer is not part of the damage, so its old and new spots are reported too.
+            let cursor = Self::draw_cursor(display, drawable, rect, &options, buffer)?;
+            let bounds = Area::new(0, 0, rect.w as u64, rect.h as u64);
+            dirty.extend(
+                [display.replace_last_cursor(stream, cursor), cursor]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|area| area.intersect(&bounds)),
+            );
+            Ok(dirty)
+        })
+    }
+
+    fn end_stream(&self, stream: StreamId) {
+        // Damage objects only live as long as the connection, so there is nothing to release
+        // without one.
+        let connection = self.connection.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(display) = connection.as_ref().filter(|display| !display.is_broken()) {
+            let _ = unsafe {
+                display.with_error_trap(|| {
+                    display.end_stream(stream);
+                    Ok(())
+                })
+            };
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::xutils::{bounding_rect, fill_uncovered, has_gaps, Rect};
+    use crate::ImageBuffer;
+    use image::Rgba;
+
+    fn rect(x: i32, y: i32, w: u32, h: u32) -> Rect {
+        Rect { x, y, w, h }
+    }
+
+    #[test]
+    fn bounding_rect_spans_all() {
+        assert_eq!(bounding_rect([]), None);
+        assert_eq!(
+            bounding_rect([rect(0, 0, 1920, 1080), rect(-1280, 200, 1280, 1024)]),
+            Some(rect(-1280, 0, 3200, 1224))
+        );
+    }
+
+    #[test]
+    fn fill_uncovered_keeps_covered_pixels() {
+        let (white, black) = (Rgba([255; 4]), Rgba([0, 0, 0, 255]));
+        let mut image = ImageBuffer::from_pixel(6, 3, white);
+        // Overlapping spans on the first row, one rect partly outside of the image.
+        fill_uncovered(
+            &mut image,
+            &[rect(0, 0, 3, 1), rect(2, 0, 2, 2), rect(5, 1, 4, 4)],
+            black,
+        );
+        let rows: Vec<String> = image
+            .rows()
+            .map(|row| row.map(|p| if *p == white { '#' } else { '.' }).collect())
+            .collect();
+        assert_eq!(rows, ["####..", "..##.#", ".....#"]);
+    }
+
+    #[test]
+    fn has_gaps_finds_uncovered_pixels() {
+        let side_by_side = [rect(0, 0, 4, 3), rect(4, 0, 2, 3)];
+        assert!(!has_gaps(&side_by_side, 6, 3));
+        // A shorter monitor on the right leaves its bottom uncovered.
+        assert!(has_gaps(&[rect(0, 0, 4, 3), rect(4, 0, 2, 2)], 6, 3));
+        // A gap between monitors that are each as tall as the image.
+        assert!(has_gaps(&[rect(0, 0, 2, 3), rect(3, 0, 3, 3)], 6, 3));
+        assert!(!has_gaps(&[rect(-1, -1, 8, 5)], 6, 3));
+        assert!(has_gaps(&[], 6, 3));
+    }
+}
@@ -0,0 +1,163 @@
+//! XComposite capture path for windows.
+//!
+//! A redirected window is rendered into an off-screen pixmap instead of straight onto the screen,
+//! so its contents can be read no matter which windows overlap it or whether it extends past the
+//! screen edges. Redirection has to happen on the top-level (frame) window; client windows are
+//! read from their frame's pixmap at their offset within the frame.
+//!
+//! Redirections are undone when the window is no longer captured through this connection, or
+//! forgotten once the window is destroyed.
+
+use std::os::raw::c_int;
+use std::time::{Duration, Instant};
+
+use x11::xlib;
+
+use super::damage::DamageTracker;
+use crate::error::{Error, Result};
+
+const COMPOSITE_REDIRECT_AUTOMATIC: c_int = 0;
+
+/// How long a newly redirected window is given to repaint.
+const REPAINT_TIMEOUT: Duration = Duration::from_millis(100);
+
+// The x11 crate has no bindings for XComposite.
+#[link(name = "Xcomposite")]
+extern "C" {
+    fn XCompositeQueryExtension(
+        dpy: *mut xlib::Display,
+        event_base: *mut c_int,
+        error_base: *mut c_int,
+    ) -> xlib::Bool;
+    fn XCompositeQueryVersion(
+        dpy: *mut xlib::Display,
+        major: *mut c_int,
+        minor: *mut c_int,
+    ) -> xlib::Status;
+    fn XCompositeRedirectWindow(dpy: *mut xlib::Display, window: xlib::Window, update: c_int);
+    fn XCompositeUnredirectWindow(dpy: *mut xlib::Display, window: xlib::Window, update: c_int);
+    fn XCompositeNameWindowPixmap(dpy: *mut xlib::Display, window: xlib::Window) -> xlib::Pixmap;
+}
+
+/// Whether the server supports naming window pixmaps, which needs XComposite 0.2.
+pub unsafe fn is_supported(display: *mut xlib::Display) -> bool {
+    if XCompositeQueryExtension(display, &mut 0, &mut 0) == xlib::False {
+        return false;
+    }
+    let (mut major, mut minor) = (0, 2);
+    XCompositeQueryVersion(display, &mut major, &mut minor) != 0 && (major, minor) >= (0, 2)
+}
+
+/// Redirection of a top-level window, undone on drop unless the window was destroyed.
+///
+/// Automatic redirection leaves painting the screen to the server and coexists with compositing
+/// managers. Parts of the window that were obscured when it was redirected only become readable
+/// once its client repaints them.
+pub struct Redirection {
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    destroyed: bool,
+    /// Damage of the window since it was redirected, kept until it was repainted or the
+    /// deadline passed.
+    repaint: Option<(DamageTracker, Instant)>,
+}
+
+impl Redirection {
+    /// Redirects `window`, observing its repaint through `tracker`, which has to be created
+    /// before so the repaint cannot slip through. Without a tracker the repaint is not waited
+    /// for.
+    ///
+    /// Must be called inside an error trap, since the window may be gone already.
+    pub unsafe fn new(
+        display: *mut xlib::Display,
+        window: xlib::Window,
+        tracker: Option<DamageTracker>,
+    ) -> Self {
+        // Structure events tell when the window is destroyed.
+        xlib::XSelectInput(display, window, xlib::StructureNotifyMask);
+        XCompositeRedirectWindow(display, window, COMPOSITE_REDIRECT_AUTOMATIC);
+        // Redirecting damages the window by itself, which says nothing about its contents.
+        let repaint = tracker.map(|mut tracker| {
+            xlib::XSync(display, xlib::False);
+            tracker.take();
+            (tracker, Instant::now() + REPAINT_TIMEOUT)
+        });
+        Redirection {
+            display,
+            window,
+            destroyed: false,
+            repaint,
+        }
+    }
+
+    /// Returns until when to wait for the client to repaint the window, or `None` once it did or
+    /// the wait timed out.
+    pub unsafe fn awaiting_repaint(&mut self) -> Option<Instant> {
+        let (tracker, deadline) = self.repaint.as_mut()?;
+        if tracker.take().is_empty() && Instant::now() < *deadline {
+            return Some(*deadline);
+        }
+        self.repaint = None;
+        None
+    }
+
+    /// Drops the queued structure events of the window and returns whether it was destroyed.
+    pub unsafe fn is_destroyed(&mut self) -> bool {
+        let mut event = std::mem::zeroed::<xlib::XEvent>();
+        while xlib::XCheckWindowEvent(
+            self.display,
+            self.window,
+            xlib::StructureNotifyMask,
+            &mut event,
+        ) != xlib::False
+        {
+            self.destroyed |= event.get_type() == xlib::DestroyNotify;
+        }
+        self.destroyed
+    }
+}
+
+impl Drop for Redirection {
+    fn drop(&mut self) {
+        if self.destroyed {
+            return;
+        }
+        unsafe {
+            XCompositeUnredirectWindow(self.display, self.window, COMPOSITE_REDIRECT_AUTOMATIC);
+            xlib::XSelectInput(self.display, self.window, xlib::NoEventMask);
+        }
+    }
+}
+
+/// The off-screen contents of a redirected window, freed on drop.
+pub struct WindowPixmap {
+    display: *mut xlib::Display,
+    pixmap: xlib::Pixmap,
+}
+
+impl WindowPixmap {
+    /// Must be called inside an error trap, since unviewable windows fail asynchronously.
+    pub unsafe fn new(display: *mut xlib::Display, window: xlib::Window) -> Result<Self> {
+        let pixmap = XCompositeNameWindowPixmap(display, window);
+        xlib::XSync(display, xlib::False);
+        if pixmap == 0 {
+            return Err(Error::backend(
+                "x11-composite",
+                "XCompositeNameWindowPixmap failed",
+            ));
+        }
+        Ok(WindowPixmap { display, pixmap })
+    }
+
+    pub fn pixmap(&self) -> xlib::Pixmap {
+        self.pixmap
+    }
+}
+
+impl Drop for WindowPixmap {
+    fn drop(&mut self) {
+        unsafe {
+            xlib::XFreePixmap(self.display, self.pixmap);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Scoped handling of X protocol errors.
+//!
+//! Xlib reports protocol errors asynchronously through a single process-wide handler, and the
+//! default handler terminates the process. We install our own handler once and let callers
+//! register a trap for their display connection. Errors on a trapped display are recorded and
+//! turned into `Error::XProtocol`; errors on any other display are forwarded to the handler that
+//! was active before ours.
+//!
+//! Fatal I/O errors are handled per display, see `set_io_error_exit_flag`.
+
+use std::ffi::c_void;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Once};
+
+use x11::xlib;
+
+use crate::error::{Error, Result};
+
+type ErrorHandler = unsafe extern "C" fn(*mut xlib::Display, *mut xlib::XErrorEvent) -> c_int;
+
+struct Trap {
+    display: usize,
+    error: Option<Error>,
+}
+
+static INSTALL: Once = Once::new();
+static PREVIOUS_HANDLER: Mutex<Option<ErrorHandler>> = Mutex::new(None);
+static TRAPS: Mutex<Vec<Trap>> = Mutex::new(Vec::new());
+
+unsafe extern "C" fn handle_error(
+    display: *mut xlib::Display,
+    event: *mut xlib::XErrorEvent,
+) -> c_int {
+    let event = *event;
+    let mut traps = TRAPS.lock().unwrap_or_else(|e| e.into_inner());
+    // Nested traps on the same display report to the innermost one.
+    if let Some(trap) = traps.iter_mut().rfind(|t| t.display == display as usize) {
+        if trap.error.is_none() {
+            let mut text = [0 as c_char; 256];
+            xlib::XGetErrorText(
+                display,
+                event.error_code as c_int,
+                text.as_mut_ptr(),
+                text.len() as c_int,
+            );
+            trap.error = Some(Error::XProtocol {
+                error_code: event.error_code,
+                request_code: event.request_code,
+                minor_code: event.minor_code,
+                resource_id: event.resourceid,
+                description: std::ffi::CStr::from_ptr(text.as_ptr())
+                    .to_string_lossy()
+                    .into_owned(),
+            });
+        }
+        return 0;
+    }
+    drop(traps);
+    match *PREVIOUS_HANDLER.lock().unwrap_or_else(|e| e.into_inner()) {
+        Some(previous) => previous(display, &event as *const _ as *mut _),
+        None => 0,
+    }
+}
+
+/// Records X protocol errors on a display for as long as it is alive.
+pub struct ErrorTrap {
+    display: *mut xlib::Display,
+}
+
+impl ErrorTrap {
+    /// Flushes pending requests and starts recording errors on `display`.
+    pub unsafe fn new(display: *mut xlib::Display) -> Self {
+        INSTALL.call_once(|| {
+            let previous = xlib::XSetErrorHandler(Some(handle_error));
+            *PREVIOUS_HANDLER.lock().unwrap_or_else(|e| e.into_inner()) = previous;
+        });
+        // Errors caused by earlier requests belong to whoever issued them.
+        xlib::XSync(display, xlib::False);
+        TRAPS.lock().unwrap_or_else(|e| e.into_inner()).push(Trap {
+            display: display as usize,
+            error: None,
+        });
+        ErrorTrap { display }
+    }
+
+    /// Waits for the server to process all requests and returns the first recorded error.
+    pub unsafe fn check(&self) -> Result<()> {
+        xlib::XSync(self.display, xlib::False);
+        let mut traps = TRAPS.lock().unwrap_or_else(|e| e.into_inner());
+        let trap = traps
+            .iter_mut()
+            .rfind(|t| t.display == self.display as usize);
+        match trap.and_then(|t| t.error.take()) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for ErrorTrap {
+    fn drop(&mut self) {
+        let mut traps = TRAPS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(index) = traps
+            .iter()
+            .rposition(|t| t.display == self.display as usize)
+        {
+            traps.remove(index);
+        }
+    }
+}
+
+type IOErrorExitHandler = unsafe extern "C" fn(*mut xlib::Display, *mut c_void);
+type SetIOErrorExitHandler =
+    unsafe extern "C" fn(*mut xlib::Display, Option<IOErrorExitHandler>, *mut c_void);
+
+unsafe extern "C" fn handle_io_error_exit(_display: *mut xlib::Display, user_data: *mut c_void) {
+    (*(user_data as *const AtomicBool)).store(true, Ordering::SeqCst);
+}
+
+/// Keeps a fatal I/O error on `display` (e.g. a restarted X server) from terminating the process.
+///
+/// `broken` is set once the connection is lost and must outlive the display. Returns `false` if
+/// the installed libX11 is older than 1.7 and does not support per-display exit handlers, in
+/// which case Xlib keeps its default behaviour.
+pub unsafe fn set_io_error_exit_flag(
+    display: *mut xlib::Display,
+    broken: *const AtomicBool,
+) -> bool {
+    // Resolved at runtime so older libX11 versions can still load the library.
+    let symbol = libc::dlsym(libc::RTLD_DEFAULT, c"XSetIOErrorExitHandler".as_ptr());
+    if symbol.is_null() {
+        return false;
+    }
+    let set_handler: SetIOErrorExitHandler = std::mem::transmute(symbol);
+    set_handler(display, Some(handle_io_error_exit), broken as *mut c_void);
+    true
+}
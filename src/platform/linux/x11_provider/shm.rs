@@ -0,0 +1,162 @@
+//! MIT-SHM capture path.
+//!
+//! `XShmGetImage` lets the server write pixels straight into a shared memory segment instead of
+//! sending them over the socket, which is what makes high frame rate captures feasible. The
+//! segment is kept alive between captures and only reallocated when a larger image is requested.
+
+use std::os::raw::c_char;
+
+use x11::{xlib, xshm};
+
+use super::error_trap::ErrorTrap;
+use crate::error::{Error, Result};
+
+// The x11 crate only links libXext for its `dpms` feature.
+#[link(name = "Xext")]
+extern "C" {}
+
+pub struct ShmSegment {
+    display: *mut xlib::Display,
+    // Boxed because Xlib keeps a pointer to it in every image created on this segment.
+    info: Box<xshm::XShmSegmentInfo>,
+    capacity: usize,
+}
+
+impl ShmSegment {
+    pub unsafe fn is_supported(display: *mut xlib::Display) -> bool {
+        xshm::XShmQueryExtension(display) != xlib::False
+    }
+
+    /// Creates a segment of at least `capacity` bytes and attaches it to the server.
+    ///
+    /// Attaching fails asynchronously for clients that do not share memory with the server
+    /// (e.g. over `ssh -X`), so the attachment is checked under its own error trap before the
+    /// segment is handed out.
+    pub unsafe fn new(display: *mut xlib::Display, capacity: usize) -> Result<Self> {
+        let shmid = libc::shmget(libc::IPC_PRIVATE, capacity, libc::IPC_CREAT | 0o600);
+        if shmid < 0 {
+            return Err(Error::backend("x11-shm", std::io::Error::last_os_error()));
+        }
+        let shmaddr = libc::shmat(shmid, std::ptr::null(), 0);
+        if shmaddr as isize == -1 {
+            let error = std::io::Error::last_os_error();
+            libc::shmctl(shmid, libc::IPC_RMID, std::ptr::null_mut());
+            return Err(Error::backend("x11-shm", error));
+        }
+        let segment = Self::attach(display, shmid, shmaddr, capacity);
+        // Marked for removal right away, so the kernel frees it once both sides detached.
+        libc::shmctl(shmid, libc::IPC_RMID, std::ptr::null_mut());
+        segment
+    }
+
+    /// Attaches the segment `shmid`, mapped at `shmaddr`, to the server. On failure the mapping
+    /// is released without detaching from the server, which never attached it.
+    unsafe fn attach(
+        display: *mut xlib::Display,
+        shmid: i32,
+        shmaddr: *mut libc::c_void,
+        capacity: usize,
+    ) -> Result<Self> {
+        let mut info = Box::new(xshm::XShmSegmentInfo {
+            shmseg: 0,
+            shmid,
+            shmaddr: shmaddr as *mut c_char,
+            readOnly: xlib::False,
+        });
+        let trap = ErrorTrap::new(display);
+        let attached = xshm::XShmAttach(display, &mut *info) != xlib::False;
+        let result = match trap.check() {
+            Err(error) => Err(error),
+            Ok(()) if !attached => Err(Error::backend("x11-shm", "XShmAttach failed")),
+            Ok(()) => Ok(()),
+        };
+        drop(trap);
+        if let Err(error) = result {
+            libc::shmdt(shmaddr);
+            return Err(error);
+        }
+        Ok(ShmSegment {
+            display,
+            info,
+            capacity,
+        })
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Copies the given rectangle of `drawable` into the segment.
+    ///
+    /// The returned image borrows the segment's memory and is only valid until the next call.
+    /// Destroying it with `XDestroyImage` releases the image header only.
+    pub unsafe fn get_image(
+        &mut self,
+        drawable: xlib::Drawable,
+        attrs: &xlib::XWindowAttributes,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) -> Result<*mut xlib::XImage> {
+        let image = xshm::XShmCreateImage(
+            self.display,
+            attrs.visual,
+            attrs.depth as u32,
+            xlib::ZPixmap,
+            std::ptr::null_mut(),
+            &mut *self.info,
+            width,
+            height,
+        );
+        if image.is_null() {
+            return Err(Error::backend("x11-shm", "XShmCreateImage failed"));
+        }
+        if (*image).bytes_per_line as usize * height as usize > self.capacity {
+            xlib::XDestroyImage(image);
+            return Err(Error::backend("x11-shm", "segment too small"));
+        }
+        (*image).data = self.info.shmaddr;
+        const ALL_PLANES: std::os::raw::c_uint = !0;
+        if xshm::XShmGetImage(self.display, drawable, image, x, y, ALL_PLANES) == xlib::False {
+            xlib::XDestroyImage(image);
+            return Err(Error::backend("x11-shm", "XShmGetImage failed"));
+        }
+        Ok(image)
+    }
+}
+
+impl Drop for ShmSegment {
+    fn drop(&mut self) {
+        unsafe {
+            xshm::XShmDetach(self.display, &mut *self.info);
+            libc::shmdt(self.info.shmaddr as *const libc::c_void);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::xutils::XDisplayHandle;
+    use super::*;
+
+    #[test]
+    #[ignore = "needs an X server with MIT-SHM"]
+    fn failed_attach_leaves_no_pending_errors() {
+        unsafe {
+            let display = XDisplayHandle::open(None, None).expect("no X display");
+            assert!(ShmSegment::is_supported(*display));
+            let shmid = libc::shmget(libc::IPC_PRIVATE, 4096, libc::IPC_CREAT | 0o600);
+            let shmaddr = libc::shmat(shmid, std::ptr::null(), 0);
+            libc::shmctl(shmid, libc::IPC_RMID, std::ptr::null_mut());
+            // The server cannot attach a segment that does not exist.
+            let bogus = libc::shmget(libc::IPC_PRIVATE, 4096, libc::IPC_CREAT | 0o600);
+            libc::shmctl(bogus, libc::IPC_RMID, std::ptr::null_mut());
+            assert!(ShmSegment::attach(*display, bogus, shmaddr, 4096).is_err());
+            // Nothing may be left for the next request to trip over.
+            assert!(display.with_error_trap(|| Ok(())).is_ok());
+            drop(display);
+            assert!(libc::shmdt(shmaddr) == -1);
+        }
+    }
+}
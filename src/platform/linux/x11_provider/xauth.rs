@@ -0,0 +1,266 @@
+//! Minimal Xauthority file support.
+//!
+//! Xlib only reads the file named by the `XAUTHORITY` environment variable. To connect with a
+//! different file without touching the environment, we look up the cookie ourselves and hand it
+//! to Xlib with `XSetAuthorization` while the connection is opened.
+
+use std::net::{IpAddr, ToSocketAddrs};
+use std::os::raw::{c_char, c_int};
+use std::path::Path;
+use std::sync::{Mutex, Once};
+
+use x11::xlib;
+
+use crate::error::{Error, Result};
+
+const FAMILY_INTERNET: u16 = 0;
+const FAMILY_INTERNET6: u16 = 6;
+const FAMILY_LOCAL: u16 = 256;
+const FAMILY_WILD: u16 = 65535;
+const MIT_MAGIC_COOKIE: &[u8] = b"MIT-MAGIC-COOKIE-1";
+
+/// `XSetAuthorization` is process-wide, so every connection has to be opened under this lock,
+/// whether it uses a cookie or not.
+static AUTHORIZATION: Mutex<()> = Mutex::new(());
+static INIT_THREADS: Once = Once::new();
+
+#[derive(Debug)]
+pub struct Cookie {
+    name: Vec<u8>,
+    data: Vec<u8>,
+}
+
+struct Entry {
+    family: u16,
+    address: Vec<u8>,
+    number: Vec<u8>,
+    name: Vec<u8>,
+    data: Vec<u8>,
+}
+
+fn parse_entries(mut bytes: &[u8]) -> Vec<Entry> {
+    fn field(bytes: &mut &[u8]) -> Option<Vec<u8>> {
+        let len = u16::from_be_bytes([*bytes.first()?, *bytes.get(1)?]) as usize;
+        let value = bytes.get(2..2 + len)?.to_vec();
+        *bytes = &bytes[2 + len..];
+        Some(value)
+    }
+
+    let mut entries = Vec::new();
+    while bytes.len() >= 2 {
+        let family = u16::from_be_bytes([bytes[0], bytes[1]]);
+        bytes = &bytes[2..];
+        let entry = (|| {
+            Some(Entry {
+                family,
+                address: field(&mut bytes)?,
+                number: field(&mut bytes)?,
+                name: field(&mut bytes)?,
+                data: field(&mut bytes)?,
+            })
+        })();
+        match entry {
+            Some(entry) => entries.push(entry),
+            None => break,
+        }
+    }
+    entries
+}
+
+impl Entry {
+    /// Whether the entry is for `local`, the host name used by local-family entries, or one of
+    /// `addresses`.
+    fn matches_host(&self, local: &[u8], addresses: &[IpAddr]) -> bool {
+        match self.family {
+            FAMILY_WILD => true,
+            FAMILY_LOCAL => self.address == local,
+            FAMILY_INTERNET | FAMILY_INTERNET6 => addresses.iter().any(|ip| match ip {
+                IpAddr::V4(ip) => self.address == ip.octets(),
+                IpAddr::V6(ip) => self.address == ip.octets(),
+            }),
+            _ => false,
+        }
+    }
+}
+
+/// Addresses a TCP display on `host` may connect to.
+fn host_addresses(host: &str) -> Vec<IpAddr> {
+    match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => vec![ip],
+        Err(_) => (host, 0)
+            .to_socket_addrs()
+            .map(|addresses| addresses.map(|address| address.ip()).collect())
+            .unwrap_or_default(),
+    }
+}
+
+fn hostname() -> Vec<u8> {
+    let mut buf = [0u8; 256];
+    match unsafe { libc::gethostname(buf.as_mut_ptr() as *mut c_char, buf.len()) } {
+        0 => buf.iter().copied().take_while(|&b| b != 0).collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl Cookie {
+    /// Looks up the `MIT-MAGIC-COOKIE-1` entry for a display name like `:99` or `host:1.0`.
+    pub fn load(path: &Path, display: &str) -> Result<Self> {
+        let bytes = std::fs::read(path).map_err(|e| Error::backend("x11-xauth", e))?;
+        Self::find(&bytes, display).ok_or_else(|| {
+            Error::backend(
+                "x11-xauth",
+                format!("no cookie for display {} in {}", display, path.display()),
+            )
+        })
+    }
+
+    fn find(bytes: &[u8], display: &str) -> Option<Self> {
+        let (host, number) = display.rsplit_once(':').unwrap_or(("", display));
+        let number = number.split('.').next().unwrap_or_default().as_bytes();
+        // Local displays are listed under the host name, TCP displays under their addresses.
+        let (local, addresses) = match host {
+            "" | "unix" => (hostname(), Vec::new()),
+            host => (host.as_bytes().to_vec(), host_addresses(host)),
+        };
+        parse_entries(bytes)
+            .into_iter()
+            .filter(|e| e.name == MIT_MAGIC_COOKIE)
+            .filter(|e| e.number.is_empty() || e.number == number)
+            .filter(|e| e.matches_host(&local, &addresses))
+            // Entries for this exact host beat wildcard entries. Like libXau, the first of equally
+            // good entries wins, which `min_by_key` keeps.
+            .min_by_key(|e| e.family == FAMILY_WILD)
+            .map(|e| Cookie {
+                name: e.name,
+                data: e.data,
+            })
+    }
+}
+
+/// Runs `open`, which opens a display connection, with `cookie` set as the authorization.
+///
+/// Every connection is opened through here, so a cookie set for one display never leaks into a
+/// connection to another.
+pub unsafe fn open_display<T>(cookie: Option<&Cookie>, open: impl FnOnce() -> T) -> T {
+    // Before libX11 1.8, concurrent `XOpenDisplay` calls are only safe after `XInitThreads`.
+    INIT_THREADS.call_once(|| {
+        xlib::XInitThreads();
+    });
+    let _guard = AUTHORIZATION.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(cookie) = cookie else {
+        return open();
+    };
+    xlib::XSetAuthorization(
+        cookie.name.as_ptr() as *mut c_char,
+        cookie.name.len() as c_int,
+        cookie.data.as_ptr() as *mut c_char,
+        cookie.data.len() as c_int,
+    );
+    let result = open();
+    xlib::XSetAuthorization(std::ptr::null_mut(), 0, std::ptr::null_mut(), 0);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(family: u16, address: &[u8], number: &str, name: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut bytes = family.to_be_bytes().to_vec();
+        for field in [address, number.as_bytes(), name, data] {
+            bytes.extend_from_slice(&(field.len() as u16).to_be_bytes());
+            bytes.extend_from_slice(field);
+        }
+        bytes
+    }
+
+    fn cookie(family: u16, address: &[u8], number: &str, data: &[u8]) -> Vec<u8> {
+        entry(family, address, number, MIT_MAGIC_COOKIE, data)
+    }
+
+    fn find(bytes: &[u8], display: &str) -> Option<Vec<u8>> {
+        Cookie::find(bytes, display).map(|cookie| cookie.data)
+    }
+
+    #[test]
+    fn parses_entries_and_ignores_truncated_tail() {
+        let mut bytes = cookie(FAMILY_LOCAL, b"host", "0", b"abc");
+        bytes.extend(entry(FAMILY_WILD, b"", "", b"XDM-AUTHORIZATION-1", b"xyz"));
+        bytes.extend(&cookie(FAMILY_LOCAL, b"host", "1", b"def")[..7]);
+        let entries = parse_entries(&bytes);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].family, FAMILY_LOCAL);
+        assert_eq!(entries[0].address, b"host");
+        assert_eq!(entries[0].number, b"0");
+        assert_eq!(entries[0].data, b"abc");
+        assert_eq!(entries[1].name, b"XDM-AUTHORIZATION-1");
+    }
+
+    #[test]
+    fn parses_empty_and_garbage_input() {
+        assert!(parse_entries(&[]).is_empty());
+        assert!(parse_entries(&[0xFF]).is_empty());
+        assert!(parse_entries(&[0, 0, 0, 9, 1]).is_empty());
+    }
+
+    #[test]
+    fn local_display_matches_own_host_name_only() {
+        let host = hostname();
+        let mut bytes = cookie(FAMILY_LOCAL, b"some-other-host", "99", b"other");
+        bytes.extend(cookie(FAMILY_LOCAL, &host, "99", b"mine"));
+        assert_eq!(find(&bytes, ":99").as_deref(), Some(&b"mine"[..]));
+        assert_eq!(find(&bytes, "unix:99.0").as_deref(), Some(&b"mine"[..]));
+        assert_eq!(find(&bytes[..bytes.len() / 2], ":99"), None);
+    }
+
+    #[test]
+    fn display_number_must_match_unless_empty() {
+        let host = hostname();
+        let bytes = cookie(FAMILY_LOCAL, &host, "1", b"one");
+        assert_eq!(find(&bytes, ":2"), None);
+        let bytes = cookie(FAMILY_LOCAL, &host, "", b"any");
+        assert_eq!(find(&bytes, ":2").as_deref(), Some(&b"any"[..]));
+    }
+
+    #[test]
+    fn tcp_display_matches_internet_entries() {
+        let mut bytes = cookie(FAMILY_INTERNET, &[10, 0, 0, 2], "1", b"other");
+        bytes.extend(cookie(FAMILY_INTERNET, &[127, 0, 0, 1], "1", b"v4"));
+        bytes.extend(cookie(
+            FAMILY_INTERNET6,
+            &"::1".parse::<std::net::Ipv6Addr>().unwrap().octets(),
+            "1",
+            b"v6",
+        ));
+        assert_eq!(find(&bytes, "127.0.0.1:1").as_deref(), Some(&b"v4"[..]));
+        assert_eq!(find(&bytes, "[::1]:1").as_deref(), Some(&b"v6"[..]));
+        assert_eq!(find(&bytes, "10.0.0.3:1"), None);
+    }
+
+    #[test]
+    fn exact_entries_beat_wildcards_and_other_schemes_are_skipped() {
+        let host = hostname();
+        let mut bytes = cookie(FAMILY_WILD, b"", "0", b"wild");
+        bytes.extend(entry(
+            FAMILY_LOCAL,
+            &host,
+            "0",
+            b"XDM-AUTHORIZATION-1",
+            b"xdm",
+        ));
+        assert_eq!(find(&bytes, ":0").as_deref(), Some(&b"wild"[..]));
+        bytes.extend(cookie(FAMILY_LOCAL, &host, "0", b"exact"));
+        assert_eq!(find(&bytes, ":0").as_deref(), Some(&b"exact"[..]));
+    }
+
+    #[test]
+    fn first_of_equally_good_entries_wins() {
+        let host = hostname();
+        let mut bytes = cookie(FAMILY_WILD, b"", "0", b"wild");
+        bytes.extend(cookie(FAMILY_WILD, b"", "0", b"later wild"));
+        assert_eq!(find(&bytes, ":0").as_deref(), Some(&b"wild"[..]));
+        bytes.extend(cookie(FAMILY_LOCAL, &host, "", b"first"));
+        bytes.extend(cookie(FAMILY_LOCAL, &host, "0", b"second"));
+        assert_eq!(find(&bytes, ":0").as_deref(), Some(&b"first"[..]));
+    }
+}
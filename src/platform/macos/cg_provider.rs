@@ -75,7 +75,10 @@ impl WindowCaptureProvider for CGProvider {
             }
 
             ImageBuffer::from_raw(cg_image.width() as u32, cg_image.height() as u32, new_buf)
-                .ok_or(Error::WindowCaptureFailed)
+                .ok_or(Error::InvalidImageData {
+                    width: cg_image.width() as u32,
+                    height: cg_image.height() as u32,
+                })
         }
     }
 
@@ -149,7 +152,7 @@ impl WindowCaptureProvider for CGProvider {
 
             match window_id {
                 Some(id) => self.capture_window(id as u64),
-                None => Err(Error::WindowCaptureFailed),
+                None => Err(Error::NoFocusedWindow),
             }
         }
     }
@@ -162,9 +165,12 @@ impl ScreenCaptureProvider for CGProvider {
             let mut buf = Vec::from(cg_image.data().bytes());
             buf.chunks_exact_mut(4).for_each(|c| c.swap(0, 2));
             ImageBuffer::from_raw(cg_image.width() as u32, cg_image.height() as u32, buf)
-                .ok_or(Error::WindowCaptureFailed)
+                .ok_or(Error::InvalidImageData {
+                    width: cg_image.width() as u32,
+                    height: cg_image.height() as u32,
+                })
         } else {
-            Err(Error::WindowCaptureFailed)
+            Err(Error::ScreenNotFound(screen_id))
         }
     }
 
@@ -176,12 +182,47 @@ impl ScreenCaptureProvider for CGProvider {
 
 impl AreaCaptureProvider for CGProvider {
     fn capture_area(&self, _area: Area) -> Result<ImageBuffer> {
-        unimplemented!()
+        Err(Error::Unsupported(Capability::AreaCapture))
     }
 }
 
 impl FullCaptureProvider for CGProvider {
     fn capture_full(&self) -> Result<ImageBuffer> {
-        unimplemented!()
+        Err(Error::Unsupported(Capability::FullCapture))
+    }
+}
+
+impl WindowEnumerationProvider for CGProvider {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>> {
+        Err(Error::Unsupported(Capability::WindowEnumeration))
+    }
+}
+
+impl ScreenEnumerationProvider for CGProvider {
+    fn list_screens(&self) -> Result<Vec<Screen>> {
+        Err(Error::Unsupported(Capability::ScreenEnumeration))
+    }
+}
+
+impl CaptureBackend for CGProvider {
+    fn name(&self) -> &'static str {
+        "coregraphics"
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            window_capture: true,
+            screen_capture: true,
+            area_capture: false,
+            full_capture: false,
+            window_enumeration: false,
+            screen_enumeration: false,
+            cursor: false,
+            // Window captures always pass `kCGWindowImageBoundsIgnoreFraming`.
+            decorations: false,
+            alpha: false,
+            streaming: false,
+            multi_monitor: true,
+        }
     }
 }
@@ -0,0 +1,5 @@
+mod mock_provider;
+mod pattern;
+
+pub use self::mock_provider::MockProvider;
+pub use self::pattern::Pattern;
@@ -0,0 +1,538 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use image::{imageops, Rgba};
+
+use super::Pattern;
+use crate::{error::*, shared::*, traits::*, ImageBuffer};
+
+type Fault = Box<dyn Fn(Capability) -> Option<Error> + Send + Sync>;
+
+/// Deterministic provider serving synthetic images, for testing code that depends on capture.
+///
+/// Each screen is rendered separately from the configured [`Pattern`] and placed at its area on
+/// the desktop; area and full captures are cropped from that desktop. Windows are rendered at
+/// their own size, labelled with their title.
+pub struct MockProvider {
+    pattern: Pattern,
+    screens: Vec<Screen>,
+    windows: Vec<WindowInfo>,
+    focused_window: Option<WindowId>,
+    pointer: Option<(i64, i64)>,
+    latency: Duration,
+    errors: Mutex<VecDeque<Error>>,
+    fault: Option<Fault>,
+    calls: AtomicUsize,
+}
+
+impl Default for MockProvider {
+    fn default() -> Self {
+        MockProvider {
+            pattern: Pattern::default(),
+            screens: vec![Screen {
+                id: 0,
+                name: "MOCK-1".to_string(),
+                area: Area::new(0, 0, 1920, 1080),
+                rotation: Rotation::Normal,
+                primary: true,
+                refresh_rate: Some(60.0),
+                scale_factor: 1.0,
+            }],
+            windows: vec![WindowInfo {
+                id: 1,
+                title: "Mock window".to_string(),
+                class: Some("mock".to_string()),
+                instance: Some("mock".to_string()),
+                pid: Some(std::process::id()),
+                area: Area::new(100, 100, 640, 480),
+                desktop: Some(0),
+                mapped: true,
+                visible: true,
+            }],
+            focused_window: Some(1),
+            pointer: Some((420, 340)),
+            latency: Duration::ZERO,
+            errors: Mutex::new(VecDeque::new()),
+            fault: None,
+            calls: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Provider for MockProvider {
+    fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl MockProvider {
+    pub fn with_pattern(mut self, pattern: Pattern) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    pub fn with_screens(mut self, screens: Vec<Screen>) -> Self {
+        self.screens = screens;
+        self
+    }
+
+    /// Sets the window list, in stacking order from bottom to top.
+    pub fn with_windows(mut self, windows: Vec<WindowInfo>) -> Self {
+        self.windows = windows;
+        self
+    }
+
+    pub fn with_focused_window(mut self, window_id: Option<WindowId>) -> Self {
+        self.focused_window = window_id;
+        self
+    }
+
+    /// Sets the pointer position in desktop coordinates, or `None` if it cannot be located.
+    pub fn with_pointer(mut self, position: Option<(i64, i64)>) -> Self {
+        self.pointer = position;
+        self
+    }
+
+    /// Delays every call by `latency`.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Calls `fault` before every operation and fails it with the returned error, if any.
+    pub fn with_fault(
+        mut self,
+        fault: impl Fn(Capability) -> Option<Error> + Send + Sync + 'static,
+    ) -> Self {
+        self.fault = Some(Box::new(fault));
+        self
+    }
+
+    /// Fails the next call with `error`. Queued errors are returned in order.
+    pub fn fail_next(&self, error: Error) {
+        self.errors
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push_back(error);
+    }
+
+    /// Number of calls made so far, including failed ones.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    fn begin(&self, capability: Capability) -> Result<()> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        if !self.latency.is_zero() {
+            std::thread::sleep(self.latency);
+        }
+        let queued = self
+            .errors
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop_front();
+        match queued.or_else(|| self.fault.as_ref().and_then(|fault| fault(capability))) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    fn screen(&self, screen_id: ScreenId) -> Result<&Screen> {
+        self.screens
+            .iter()
+            .find(|screen| screen.id == screen_id)
+            .ok_or(Error::ScreenNotFound(screen_id))
+    }
+
+    fn window(&self, window_id: WindowId) -> Result<&WindowInfo> {
+        let window = self
+            .windows
+            .iter()
+            .find(|window| window.id == window_id)
+            .ok_or(Error::WindowNotFound(window_id))?;
+        if !window.mapped || !window.visible {
+            return Err(Error::WindowNotViewable(window_id));
+        }
+        Ok(window)
+    }
+
+    /// The primary screen, or the first one if none is marked primary.
+    fn current_screen(&self) -> Result<&Screen> {
+        self.screens
+            .iter()
+            .find(|screen| screen.primary)
+            .or_else(|| self.screens.first())
+            .ok_or(Error::ScreenNotFound(0))
+    }
+
+    /// The topmost visible window that contains the pointer.
+    fn window_under_cursor(&self) -> Result<WindowId> {
+        let (x, y) = self.pointer.ok_or(Error::Unsupported(Capability::Cursor))?;
+        self.windows
+            .iter()
+            .rev()
+            .find(|window| {
+                let area = window.area;
+                window.mapped
+                    && window.visible
+                    && x >= area.x
+                    && y >= area.y
+                    && x < area.x + area.width as i64
+                    && y < area.y + area.height as i64
+            })
+            .map(|window| window.id)
+            .ok_or(Error::NoWindowUnderCursor)
+    }
+
+    /// Bounding box of all screens.
+    fn desktop_area(&self) -> Area {
+        let mut screens = self.screens.iter().map(|screen| screen.area);
+        let Some(first) = screens.next() else {
+            return Area::new(0, 0, 0, 0);
+        };
+        let (mut left, mut top) = (first.x, first.y);
+        let (mut right, mut bottom) = (first.x + first.width as i64, first.y + first.height as i64);
+        for area in screens {
+            left = left.min(area.x);
+            top = top.min(area.y);
+            right = right.max(area.x + area.width as i64);
+            bottom = bottom.max(area.y + area.height as i64);
+        }
+        Area::new(left, top, (right - left) as u64, (bottom - top) as u64)
+    }
+
+    fn render_desktop(&self, desktop: Area) -> Result<ImageBuffer> {
+        let mut image = ImageBuffer::from_pixel(
+            desktop.width.try_into()?,
+            desktop.height.try_into()?,
+            Rgba([0, 0, 0, 255]),
+        );
+        for screen in &self.screens {
+            let rendered = self.pattern.render(
+                screen.area.width.try_into()?,
+                screen.area.height.try_into()?,
+                &screen.name,
+            );
+            imageops::replace(
+                &mut image,
+                &rendered,
+                screen.area.x - desktop.x,
+                screen.area.y - desktop.y,
+            );
+        }
+        Ok(image)
+    }
+
+    fn render_window(&self, window_id: WindowId) -> Result<ImageBuffer> {
+        let window = self.window(window_id)?;
+        Ok(self.pattern.render(
+            window.area.width.try_into()?,
+            window.area.height.try_into()?,
+            &window.title,
+        ))
+    }
+
+    fn crop(&self, area: Area) -> Result<ImageBuffer> {
+        let desktop = self.desktop_area();
+        let inside = area.x >= desktop.x
+            && area.y >= desktop.y
+            && area.x + area.width as i64 <= desktop.x + desktop.width as i64
+            && area.y + area.height as i64 <= desktop.y + desktop.height as i64;
+        if !inside {
+            return Err(Error::AreaOutOfBounds {
+                area,
+                bounds: desktop,
+            });
+        }
+        let image = self.render_desktop(desktop)?;
+        Ok(imageops::crop_imm(
+            &image,
+            (area.x - desktop.x).try_into()?,
+            (area.y - desktop.y).try_into()?,
+            area.width.try_into()?,
+            area.height.try_into()?,
+        )
+        .to_image())
+    }
+}
+
+impl WindowCaptureProvider for MockProvider {
+    fn capture_window(&self, window_id: WindowId) -> Result<ImageBuffer> {
+        self.begin(Capability::WindowCapture)?;
+        self.render_window(window_id)
+    }
+
+    fn capture_focused_window(&self) -> Result<ImageBuffer> {
+        self.begin(Capability::WindowCapture)?;
+        self.render_window(self.focused_window.ok_or(Error::NoFocusedWindow)?)
+    }
+}
+
+impl ScreenCaptureProvider for MockProvider {
+    fn capture_screen(&self, screen_id: ScreenId) -> Result<ImageBuffer> {
+        self.begin(Capability::ScreenCapture)?;
+        self.crop(self.screen(screen_id)?.area)
+    }
+
+    fn capture_current_screen(&self) -> Result<ImageBuffer> {
+        self.begin(Capability::ScreenCapture)?;
+        self.crop(self.current_screen()?.area)
+    }
+}
+
+impl AreaCaptureProvider for MockProvider {
+    fn capture_area(&self, area: Area) -> Result<ImageBuffer> {
+        self.begin(Capability::AreaCapture)?;
+        self.crop(area)
+    }
+}
+
+impl FullCaptureProvider for MockProvider {
+    fn capture_full(&self) -> Result<ImageBuffer> {
+        self.begin(Capability::FullCapture)?;
+        self.render_desktop(self.desktop_area())
+    }
+}
+
+impl WindowEnumerationProvider for MockProvider {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>> {
+        self.begin(Capability::WindowEnumeration)?;
+        Ok(self.windows.clone())
+    }
+}
+
+impl ScreenEnumerationProvider for MockProvider {
+    fn list_screens(&self) -> Result<Vec<Screen>> {
+        self.begin(Capability::ScreenEnumeration)?;
+        Ok(self.screens.clone())
+    }
+}
+
+impl CaptureBackend for MockProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn resolve_target(&self, target: &CaptureTarget) -> Result<CaptureTarget> {
+        match *target {
+            CaptureTarget::FocusedWindow => self
+                .focused_window
+                .map(CaptureTarget::Window)
+                .ok_or(Error::NoFocusedWindow),
+            CaptureTarget::CurrentScreen => Ok(CaptureTarget::Screen(self.current_screen()?.id)),
+            CaptureTarget::WindowUnderCursor => {
+                self.window_under_cursor().map(CaptureTarget::Window)
+            }
+            target => Ok(target),
+        }
+    }
+
+    /// Describes the capture from the configured screens and windows, so that it counts as a
+    /// single call and queued errors reach the capture itself.
+    fn capture_with_metadata(&self, target: &CaptureTarget) -> Result<Screenshot> {
+        let timestamp = SystemTime::now();
+        let image = self.capture(target)?;
+        let target = self.resolve_target(target)?;
+        let (window, origin) = match target {
+            CaptureTarget::Window(window_id) => {
+                let window = self.window(window_id)?.clone();
+                let origin = (window.area.x, window.area.y);
+                (Some(window), origin)
+            }
+            CaptureTarget::Screen(screen_id) => {
+                let area = self.screen(screen_id)?.area;
+                (None, (area.x, area.y))
+            }
+            CaptureTarget::Area(area) => (None, (area.x, area.y)),
+            _ => {
+                let area = self.desktop_area();
+                (None, (area.x, area.y))
+            }
+        };
+        let area = Area::new(
+            origin.0,
+            origin.1,
+            image.width() as u64,
+            image.height() as u64,
+        );
+        let metadata = CaptureMetadata::new(
+            target.kind(),
+            area,
+            window,
+            &self.screens,
+            self.name(),
+            timestamp,
+        );
+        Ok(Screenshot { image, metadata })
+    }
+
+    fn capture_desktop(&self) -> Result<DesktopCapture> {
+        self.begin(Capability::FullCapture)?;
+        let bounds = self.desktop_area();
+        Ok(DesktopCapture {
+            image: self.render_desktop(bounds)?,
+            bounds,
+            screens: self.screens.clone(),
+        })
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            window_capture: true,
+            screen_capture: true,
+            area_capture: true,
+            full_capture: true,
+            window_enumeration: true,
+            screen_enumeration: true,
+            cursor: false,
+            decorations: false,
+            alpha: matches!(self.pattern, Pattern::Image(_)),
+            streaming: true,
+            multi_monitor: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::Capture;
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+    fn screen(id: ScreenId, area: Area) -> Screen {
+        Screen {
+            id,
+            name: format!("S{id}"),
+            area,
+            rotation: Rotation::Normal,
+            primary: id == 0,
+            refresh_rate: None,
+            scale_factor: 1.0,
+        }
+    }
+
+    /// Two screens side by side, the second one lower than the first.
+    fn dual_screen() -> MockProvider {
+        MockProvider::new()
+            .with_pattern(Pattern::Solid(RED))
+            .with_screens(vec![
+                screen(0, Area::new(0, 0, 100, 50)),
+                screen(1, Area::new(100, 20, 60, 40)),
+            ])
+    }
+
+    #[test]
+    fn crops_across_screens() {
+        let provider = dual_screen();
+        let full = provider.capture_full().unwrap();
+        assert_eq!(full.dimensions(), (160, 60));
+        // Not covered by any screen.
+        assert_eq!(*full.get_pixel(150, 5), Rgba([0, 0, 0, 255]));
+        assert_eq!(*full.get_pixel(150, 25), RED);
+
+        let area = provider.capture_area(Area::new(90, 10, 20, 20)).unwrap();
+        assert_eq!(area.dimensions(), (20, 20));
+        assert_eq!(*area.get_pixel(5, 5), RED);
+        assert_eq!(*area.get_pixel(15, 5), Rgba([0, 0, 0, 255]));
+        assert_eq!(*area.get_pixel(15, 15), RED);
+
+        let screen = provider.capture_screen(1).unwrap();
+        assert_eq!(screen.dimensions(), (60, 40));
+        assert!(matches!(
+            provider.capture_screen(2),
+            Err(Error::ScreenNotFound(2))
+        ));
+    }
+
+    #[test]
+    fn rejects_areas_outside_the_desktop() {
+        let provider = dual_screen();
+        for area in [Area::new(-1, 0, 10, 10), Area::new(150, 50, 11, 11)] {
+            match provider.capture_area(area) {
+                Err(Error::AreaOutOfBounds {
+                    area: rejected,
+                    bounds,
+                }) => {
+                    assert_eq!(rejected, area);
+                    assert_eq!(bounds, Area::new(0, 0, 160, 60));
+                }
+                other => panic!("unexpected result: {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn queued_errors_come_before_faults() {
+        let provider = MockProvider::new().with_fault(|capability| {
+            (capability == Capability::AreaCapture).then_some(Error::ConnectionLost)
+        });
+        provider.fail_next(Error::NoFocusedWindow);
+        provider.fail_next(Error::ScreenNotFound(7));
+
+        assert!(matches!(
+            provider.capture_full(),
+            Err(Error::NoFocusedWindow)
+        ));
+        assert!(matches!(
+            provider.capture_area(Area::new(0, 0, 1, 1)),
+            Err(Error::ScreenNotFound(7))
+        ));
+        assert!(matches!(
+            provider.capture_area(Area::new(0, 0, 1, 1)),
+            Err(Error::ConnectionLost)
+        ));
+        assert!(provider.capture_full().is_ok());
+        assert_eq!(provider.calls(), 4);
+    }
+
+    #[test]
+    fn every_capture_counts_as_a_call() {
+        let provider = MockProvider::new()
+            .with_focused_window(None)
+            .with_screens(Vec::new());
+        provider.fail_next(Error::ConnectionLost);
+
+        assert!(matches!(
+            provider.capture_focused_window(),
+            Err(Error::ConnectionLost)
+        ));
+        assert!(matches!(
+            provider.capture_focused_window(),
+            Err(Error::NoFocusedWindow)
+        ));
+        assert!(matches!(
+            provider.capture_current_screen(),
+            Err(Error::ScreenNotFound(0))
+        ));
+        assert_eq!(provider.calls(), 3);
+    }
+
+    #[test]
+    fn queued_errors_reach_described_captures() {
+        let provider = MockProvider::new().with_latency(Duration::from_millis(1));
+        provider.fail_next(Error::ConnectionLost);
+        assert!(matches!(
+            Capture::full().run(&provider),
+            Err(Error::ConnectionLost)
+        ));
+        assert_eq!(provider.calls(), 1);
+
+        let screenshot = Capture::focused_window().run(&provider).unwrap();
+        assert_eq!(screenshot.metadata.area, Area::new(100, 100, 640, 480));
+        assert_eq!(screenshot.metadata.window.map(|window| window.id), Some(1));
+        assert!(provider.capture_desktop().is_ok());
+        assert_eq!(provider.calls(), 3);
+    }
+
+    #[test]
+    fn windows_are_labelled_with_their_title() {
+        let provider = MockProvider::new();
+        let window = provider.capture_window(1).unwrap();
+        let expected = Pattern::TestPattern.render(640, 480, "Mock window");
+        assert_eq!(window, expected);
+        assert_ne!(window, Pattern::TestPattern.render(640, 480, "Other"));
+    }
+}
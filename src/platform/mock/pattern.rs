@@ -0,0 +1,215 @@
+use image::Rgba;
+
+use crate::ImageBuffer;
+
+/// Synthetic image content served by a `MockProvider`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Pattern {
+    Solid(Rgba<u8>),
+    /// Horizontal gradient from the left to the right edge.
+    Gradient {
+        from: Rgba<u8>,
+        to: Rgba<u8>,
+    },
+    /// Colour bars with the name of the captured target printed in the middle.
+    #[default]
+    TestPattern,
+    /// A user-supplied image, tiled if the target is larger.
+    Image(ImageBuffer),
+}
+
+const BARS: [[u8; 3]; 8] = [
+    [191, 191, 191],
+    [191, 191, 0],
+    [0, 191, 191],
+    [0, 191, 0],
+    [191, 0, 191],
+    [191, 0, 0],
+    [0, 0, 191],
+    [0, 0, 0],
+];
+
+impl Pattern {
+    /// Renders the pattern at the given size; `label` is only used by `TestPattern`.
+    pub fn render(&self, width: u32, height: u32, label: &str) -> ImageBuffer {
+        match self {
+            Pattern::Solid(color) => ImageBuffer::from_pixel(width, height, *color),
+            Pattern::Gradient { from, to } => {
+                let span = width.saturating_sub(1).max(1) as f32;
+                ImageBuffer::from_fn(width, height, |x, _| {
+                    let t = x as f32 / span;
+                    Rgba(std::array::from_fn(|c| {
+                        (from[c] as f32 + (to[c] as f32 - from[c] as f32) * t).round() as u8
+                    }))
+                })
+            }
+            Pattern::TestPattern => {
+                let mut image = ImageBuffer::from_fn(width, height, |x, _| {
+                    let [r, g, b] = BARS[(x as usize * BARS.len()) / width.max(1) as usize];
+                    Rgba([r, g, b, 255])
+                });
+                draw_label(&mut image, label);
+                image
+            }
+            Pattern::Image(source) if source.width() == 0 || source.height() == 0 => {
+                ImageBuffer::new(width, height)
+            }
+            Pattern::Image(source) => ImageBuffer::from_fn(width, height, |x, y| {
+                *source.get_pixel(x % source.width(), y % source.height())
+            }),
+        }
+    }
+}
+
+/// Draws `text` centered on a black box, scaled to fit the image.
+fn draw_label(image: &mut ImageBuffer, text: &str) {
+    let columns = text.chars().count() as u32;
+    if columns == 0 {
+        return;
+    }
+    // Glyphs are 3x5 with one column of spacing, plus one glyph width of padding around the text.
+    let (text_w, text_h) = (columns * 4 - 1, 5);
+    let scale = (image.width() * 4 / 5 / (text_w + 2)).min(image.height() / 6 / text_h);
+    if scale == 0 {
+        return;
+    }
+    let (box_w, box_h) = ((text_w + 2) * scale, (text_h + 2) * scale);
+    let (left, top) = ((image.width() - box_w) / 2, (image.height() - box_h) / 2);
+
+    for y in top..top + box_h {
+        for x in left..left + box_w {
+            image.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+        }
+    }
+    for (i, c) in text.chars().enumerate() {
+        let origin_x = left + scale + i as u32 * 4 * scale;
+        let origin_y = top + scale;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        image.put_pixel(
+                            origin_x + col * scale + dx,
+                            origin_y + row as u32 * scale + dy,
+                            Rgba([255, 255, 255, 255]),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 3x5 bitmap glyphs, one row per byte.
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        _ => [0b111, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+    #[test]
+    fn solid_and_gradient() {
+        let red = Rgba([255, 0, 0, 255]);
+        let solid = Pattern::Solid(red).render(3, 2, "");
+        assert!(solid.pixels().all(|pixel| *pixel == red));
+
+        let gradient = Pattern::Gradient {
+            from: Rgba([0, 0, 0, 0]),
+            to: Rgba([200, 100, 50, 255]),
+        }
+        .render(5, 2, "");
+        assert_eq!(*gradient.get_pixel(0, 1), Rgba([0, 0, 0, 0]));
+        assert_eq!(*gradient.get_pixel(2, 1), Rgba([100, 50, 25, 128]));
+        assert_eq!(*gradient.get_pixel(4, 0), Rgba([200, 100, 50, 255]));
+    }
+
+    #[test]
+    fn test_pattern_bars() {
+        let image = Pattern::TestPattern.render(80, 4, "label");
+        for (i, [r, g, b]) in BARS.into_iter().enumerate() {
+            assert_eq!(*image.get_pixel(i as u32 * 10 + 5, 0), Rgba([r, g, b, 255]));
+        }
+    }
+
+    #[test]
+    fn test_pattern_label() {
+        // One glyph at scale 10: a 50x70 box at (175, 115) with a 10 pixel margin.
+        let image = Pattern::TestPattern.render(400, 300, "A");
+        assert_eq!(*image.get_pixel(175, 115), BLACK);
+        assert_eq!(*image.get_pixel(224, 184), BLACK);
+        assert_eq!(*image.get_pixel(174, 115), Rgba([0, 191, 0, 255]));
+        // Top row of "A" is 010.
+        assert_eq!(*image.get_pixel(185, 125), BLACK);
+        assert_eq!(*image.get_pixel(195, 125), WHITE);
+        assert_eq!(*image.get_pixel(205, 125), BLACK);
+        // Middle row is 111.
+        assert_eq!(*image.get_pixel(185, 145), WHITE);
+
+        // Too small for the label, so only bars are drawn.
+        let tiny = Pattern::TestPattern.render(8, 8, "A");
+        assert!(tiny.pixels().all(|pixel| *pixel != WHITE));
+    }
+
+    #[test]
+    fn images_are_tiled() {
+        let source = ImageBuffer::from_fn(2, 1, |x, _| Rgba([x as u8, 0, 0, 255]));
+        let image = Pattern::Image(source).render(5, 3, "");
+        assert_eq!(image.get_pixel(3, 2).0, [1, 0, 0, 255]);
+        assert_eq!(image.get_pixel(4, 1).0, [0, 0, 0, 255]);
+
+        let empty = Pattern::Image(ImageBuffer::new(0, 0)).render(2, 2, "");
+        assert!(empty.pixels().all(|pixel| pixel.0 == [0; 4]));
+    }
+}
@@ -0,0 +1,5 @@
+mod manifest;
+mod replay_provider;
+
+pub use self::manifest::{Manifest, RecordedFrame, MANIFEST_FILE};
+pub use self::replay_provider::{ReplayMode, ReplayProvider};
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+use crate::shared::{Area, Screen, WindowId, WindowInfo};
+
+/// Name of the manifest inside a recording directory or archive.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Description of a recording: the monitor layout and the captured frames, in order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub screens: Vec<Screen>,
+    pub frames: Vec<RecordedFrame>,
+}
+
+/// A full-desktop capture and the window state at the time it was taken.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// Image file, relative to the manifest.
+    pub file: String,
+    /// Milliseconds since the start of the recording.
+    #[serde(default)]
+    pub timestamp_ms: u64,
+    /// Global coordinates of the top-left pixel of the image.
+    #[serde(default)]
+    pub x: i64,
+    #[serde(default)]
+    pub y: i64,
+    /// Top-level windows in stacking order, from bottom to top.
+    #[serde(default)]
+    pub windows: Vec<WindowInfo>,
+    #[serde(default)]
+    pub focused_window: Option<WindowId>,
+}
+
+impl RecordedFrame {
+    /// Global area covered by a frame image of the given size.
+    pub(crate) fn area(&self, width: u32, height: u32) -> Area {
+        Area::new(self.x, self.y, width as u64, height as u64)
+    }
+}
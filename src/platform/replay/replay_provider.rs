@@ -0,0 +1,670 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+
+use image::imageops;
+
+use super::{Manifest, RecordedFrame, MANIFEST_FILE};
+use crate::{error::*, shared::*, traits::*, ImageBuffer};
+
+const BACKEND: &str = "replay";
+
+fn replay_error<E: std::error::Error + Send + Sync + 'static>(error: E) -> Error {
+    Error::backend(BACKEND, error)
+}
+
+/// How captures advance through the recording.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplayMode {
+    /// Every capture returns the next frame; capturing past the last frame fails.
+    #[default]
+    Sequential,
+    /// Like `Sequential`, but starts over after the last frame.
+    Loop,
+    /// Frames follow their timestamps, measured from the first call; the last frame is kept.
+    Realtime,
+}
+
+enum Source {
+    Directory(PathBuf),
+    Archive(HashMap<String, Vec<u8>>),
+}
+
+impl Source {
+    /// Reads a file of the recording. Names are relative and may not leave the recording.
+    fn read(&self, name: &str) -> Result<Vec<u8>> {
+        let contained = Path::new(name)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !contained {
+            return Err(Error::backend(
+                BACKEND,
+                format!("{name} is outside of the recording"),
+            ));
+        }
+        match self {
+            Source::Directory(dir) => std::fs::read(dir.join(name)).map_err(replay_error),
+            Source::Archive(files) => files
+                .get(name.trim_start_matches("./"))
+                .cloned()
+                .ok_or_else(|| Error::backend(BACKEND, format!("{name} is missing from archive"))),
+        }
+    }
+}
+
+struct ReplayState {
+    position: usize,
+    started: Option<Instant>,
+    /// Last decoded frame, kept in `Realtime` mode where captures repeat a frame until the next
+    /// one is due.
+    cached: Option<(usize, Arc<ImageBuffer>)>,
+}
+
+/// Serves previously recorded frames, so capture code can run unchanged offline.
+///
+/// A recording is a directory or `.tar` archive holding a [`Manifest`] and one full-desktop image
+/// per frame. Area, screen and window captures are cropped from the recorded frame using the
+/// recorded screen and window geometry, with windows clipped to the frame. Each capture consumes
+/// a frame; enumeration describes the frame the next capture will return.
+pub struct ReplayProvider {
+    source: Source,
+    manifest: Manifest,
+    mode: ReplayMode,
+    state: Mutex<ReplayState>,
+}
+
+impl ReplayProvider {
+    /// Opens a recording directory or `.tar` archive.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = if path.is_dir() {
+            Source::Directory(path.to_path_buf())
+        } else {
+            let mut files = HashMap::new();
+            let mut archive = tar::Archive::new(File::open(path).map_err(replay_error)?);
+            for entry in archive.entries().map_err(replay_error)? {
+                let mut entry = entry.map_err(replay_error)?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let name = entry.path().map_err(replay_error)?;
+                let name = name.to_string_lossy().trim_start_matches("./").to_string();
+                let mut data = Vec::new();
+                entry.read_to_end(&mut data).map_err(replay_error)?;
+                files.insert(name, data);
+            }
+            Source::Archive(files)
+        };
+        let manifest =
+            serde_json::from_slice(&source.read(MANIFEST_FILE)?).map_err(replay_error)?;
+        Ok(Self::with_manifest(source, manifest))
+    }
+
+    /// Creates a provider for a manifest whose frame files are relative to `dir`.
+    pub fn from_manifest(dir: impl Into<PathBuf>, manifest: Manifest) -> Self {
+        Self::with_manifest(Source::Directory(dir.into()), manifest)
+    }
+
+    fn with_manifest(source: Source, manifest: Manifest) -> Self {
+        ReplayProvider {
+            source,
+            manifest,
+            mode: ReplayMode::default(),
+            state: Mutex::new(ReplayState {
+                position: 0,
+                started: None,
+                cached: None,
+            }),
+        }
+    }
+
+    pub fn with_mode(mut self, mode: ReplayMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Index of the frame the next capture returns.
+    pub fn position(&self) -> usize {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.current(&mut state)
+    }
+
+    /// Continues the replay at frame `index` and restarts the clock in `Realtime` mode.
+    pub fn seek(&self, index: usize) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.position = index;
+        state.started = None;
+    }
+
+    fn current(&self, state: &mut ReplayState) -> usize {
+        match self.mode {
+            ReplayMode::Sequential | ReplayMode::Loop => state.position,
+            ReplayMode::Realtime => {
+                let frames =
+                    &self.manifest.frames[state.position.min(self.manifest.frames.len())..];
+                let Some(first) = frames.first() else {
+                    return state.position;
+                };
+                let elapsed = state.started.get_or_insert_with(Instant::now).elapsed();
+                let now = first.timestamp_ms + elapsed.as_millis() as u64;
+                let played = frames.iter().take_while(|f| f.timestamp_ms <= now).count();
+                state.position + played.saturating_sub(1)
+            }
+        }
+    }
+
+    fn frame(&self, index: usize) -> Result<&RecordedFrame> {
+        self.manifest
+            .frames
+            .get(index)
+            .ok_or_else(|| Error::backend(BACKEND, "end of recording"))
+    }
+
+    /// Peeks at the frame the next capture will return.
+    fn next_frame(&self) -> Result<&RecordedFrame> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let index = self.current(&mut state);
+        self.frame(index)
+    }
+
+    /// Consumes the next frame, returning its metadata and decoded image.
+    fn take_frame(&self) -> Result<(&RecordedFrame, Arc<ImageBuffer>)> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let index = self.current(&mut state);
+        let frame = self.frame(index)?;
+        let image = match &state.cached {
+            Some((cached, image)) if *cached == index => image.clone(),
+            _ => Arc::new(
+                image::load_from_memory(&self.source.read(&frame.file)?)
+                    .map_err(replay_error)?
+                    .to_rgba8(),
+            ),
+        };
+        match self.mode {
+            ReplayMode::Realtime => state.cached = Some((index, image.clone())),
+            ReplayMode::Loop => state.position = (index + 1) % self.manifest.frames.len(),
+            ReplayMode::Sequential => state.position = index + 1,
+        }
+        Ok((frame, image))
+    }
+
+    fn crop(frame: &RecordedFrame, image: &ImageBuffer, area: Area) -> Result<ImageBuffer> {
+        let bounds = frame.area(image.width(), image.height());
+        let inside = area.x >= bounds.x
+            && area.y >= bounds.y
+            && area.right() <= bounds.right()
+            && area.bottom() <= bounds.bottom();
+        if !inside {
+            return Err(Error::AreaOutOfBounds { area, bounds });
+        }
+        Ok(imageops::crop_imm(
+            image,
+            area.x.abs_diff(bounds.x).try_into()?,
+            area.y.abs_diff(bounds.y).try_into()?,
+            area.width.try_into()?,
+            area.height.try_into()?,
+        )
+        .to_image())
+    }
+
+    /// Consumes the next frame and crops `target` from it, returning the image, the resolved
+    /// target, its area and the captured window. Windows are clipped to the frame.
+    fn capture_frame(
+        &self,
+        target: &CaptureTarget,
+    ) -> Result<(ImageBuffer, CaptureTarget, Area, Option<WindowInfo>)> {
+        // Targets that do not depend on the frame are checked before it is consumed, so a bad
+        // target leaves the replay where it was.
+        let target = match *target {
+            CaptureTarget::Screen(screen_id) => CaptureTarget::Screen(self.screen(screen_id)?.id),
+            CaptureTarget::CurrentScreen => CaptureTarget::Screen(self.current_screen()?.id),
+            CaptureTarget::WindowUnderCursor => return Err(Error::Unsupported(Capability::Cursor)),
+            target => target,
+        };
+        let (frame, image) = self.take_frame()?;
+        let target = match target {
+            CaptureTarget::FocusedWindow => {
+                CaptureTarget::Window(frame.focused_window.ok_or(Error::NoFocusedWindow)?)
+            }
+            target => target,
+        };
+        let bounds = frame.area(image.width(), image.height());
+        let (area, window) = match target {
+            CaptureTarget::Window(window_id) => {
+                let window = frame
+                    .windows
+                    .iter()
+                    .find(|window| window.id == window_id)
+                    .ok_or(Error::WindowNotFound(window_id))?;
+                if !window.mapped || !window.visible {
+                    return Err(Error::WindowNotViewable(window_id));
+                }
+                let area = window
+                    .area
+                    .intersect(&bounds)
+                    .ok_or(Error::WindowNotViewable(window_id))?;
+                (area, Some(window.clone()))
+            }
+            CaptureTarget::Screen(screen_id) => (self.screen(screen_id)?.area, None),
+            CaptureTarget::Area(area) => (area, None),
+            _ => (bounds, None),
+        };
+        let image = match area == bounds {
+            true => Arc::try_unwrap(image).unwrap_or_else(|image| (*image).clone()),
+            false => Self::crop(frame, &image, area)?,
+        };
+        Ok((image, target, area, window))
+    }
+
+    fn screen(&self, screen_id: ScreenId) -> Result<&Screen> {
+        self.manifest
+            .screens
+            .iter()
+            .find(|screen| screen.id == screen_id)
+            .ok_or(Error::ScreenNotFound(screen_id))
+    }
+
+    /// The recorded primary screen, or the first one if none is marked primary.
+    fn current_screen(&self) -> Result<&Screen> {
+        self.manifest
+            .screens
+            .iter()
+            .find(|screen| screen.primary)
+            .or_else(|| self.manifest.screens.first())
+            .ok_or(Error::ScreenNotFound(0))
+    }
+}
+
+impl WindowCaptureProvider for ReplayProvider {
+    fn capture_window(&self, window_id: WindowId) -> Result<ImageBuffer> {
+        Ok(self.capture_frame(&CaptureTarget::Window(window_id))?.0)
+    }
+
+    fn capture_focused_window(&self) -> Result<ImageBuffer> {
+        Ok(self.capture_frame(&CaptureTarget::FocusedWindow)?.0)
+    }
+}
+
+impl ScreenCaptureProvider for ReplayProvider {
+    fn capture_screen(&self, screen_id: ScreenId) -> Result<ImageBuffer> {
+        Ok(self.capture_frame(&CaptureTarget::Screen(screen_id))?.0)
+    }
+
+    /// Captures the recorded primary screen, or the first one if none is marked primary.
+    fn capture_current_screen(&self) -> Result<ImageBuffer> {
+        Ok(self.capture_frame(&CaptureTarget::CurrentScreen)?.0)
+    }
+}
+
+impl AreaCaptureProvider for ReplayProvider {
+    fn capture_area(&self, area: Area) -> Result<ImageBuffer> {
+        Ok(self.capture_frame(&CaptureTarget::Area(area))?.0)
+    }
+}
+
+impl FullCaptureProvider for ReplayProvider {
+    fn capture_full(&self) -> Result<ImageBuffer> {
+        Ok(self.capture_frame(&CaptureTarget::Full)?.0)
+    }
+}
+
+impl WindowEnumerationProvider for ReplayProvider {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>> {
+        Ok(self.next_frame()?.windows.clone())
+    }
+}
+
+impl ScreenEnumerationProvider for ReplayProvider {
+    fn list_screens(&self) -> Result<Vec<Screen>> {
+        Ok(self.manifest.screens.clone())
+    }
+}
+
+impl CaptureBackend for ReplayProvider {
+    fn name(&self) -> &'static str {
+        BACKEND
+    }
+
+    /// Resolves the focused window of the frame the next capture will return.
+    fn resolve_target(&self, target: &CaptureTarget) -> Result<CaptureTarget> {
+        match *target {
+            CaptureTarget::FocusedWindow => self
+                .next_frame()?
+                .focused_window
+                .map(CaptureTarget::Window)
+                .ok_or(Error::NoFocusedWindow),
+            CaptureTarget::CurrentScreen => Ok(CaptureTarget::Screen(self.current_screen()?.id)),
+            target => Ok(target),
+        }
+    }
+
+    /// Describes the frame the capture was cropped from, which in `Realtime` mode is not
+    /// necessarily the one enumeration described a moment earlier.
+    fn capture_with_metadata(&self, target: &CaptureTarget) -> Result<Screenshot> {
+        let timestamp = SystemTime::now();
+        let (image, target, area, window) = self.capture_frame(target)?;
+        let metadata = CaptureMetadata::new(
+            target.kind(),
+            area,
+            window,
+            &self.manifest.screens,
+            self.name(),
+            timestamp,
+        );
+        Ok(Screenshot { image, metadata })
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            window_capture: true,
+            screen_capture: true,
+            area_capture: true,
+            full_capture: true,
+            window_enumeration: true,
+            screen_enumeration: true,
+            cursor: false,
+            decorations: false,
+            alpha: false,
+            streaming: true,
+            multi_monitor: !self.manifest.screens.is_empty(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::Capture;
+    use std::time::Duration;
+
+    use image::Rgba;
+
+    /// A recording of three 20x10 frames placed at (-10, 5), showing a 4x3 window at (-5 + i, 8).
+    /// Pixel (x, y) of frame `i` is `[x, y, i, 255]`.
+    struct Fixture {
+        dir: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(name: &str, timestamps: [u64; 3]) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "libscreenshot-replay-{}-{name}",
+                std::process::id()
+            ));
+            std::fs::create_dir_all(&dir).unwrap();
+            let mut frames = Vec::new();
+            for (i, timestamp_ms) in timestamps.into_iter().enumerate() {
+                // The window moves one pixel to the right per frame.
+                let window = WindowInfo {
+                    id: 7,
+                    title: "window".to_string(),
+                    class: None,
+                    instance: None,
+                    pid: None,
+                    area: Area::new(-5 + i as i64, 8, 4, 3),
+                    desktop: None,
+                    mapped: true,
+                    visible: true,
+                };
+                let file = format!("frame-{i}.png");
+                ImageBuffer::from_fn(20, 10, |x, y| Rgba([x as u8, y as u8, i as u8, 255]))
+                    .save(dir.join(&file))
+                    .unwrap();
+                frames.push(RecordedFrame {
+                    file,
+                    timestamp_ms,
+                    x: -10,
+                    y: 5,
+                    windows: vec![window],
+                    focused_window: Some(7),
+                });
+            }
+            let manifest = Manifest {
+                screens: Vec::new(),
+                frames,
+            };
+            std::fs::write(
+                dir.join(MANIFEST_FILE),
+                serde_json::to_vec(&manifest).unwrap(),
+            )
+            .unwrap();
+            Fixture { dir }
+        }
+
+        /// Packs the recording into a `.tar` archive next to it.
+        fn archive(&self) -> PathBuf {
+            let path = self.dir.with_extension("tar");
+            let mut builder = tar::Builder::new(File::create(&path).unwrap());
+            builder.append_dir_all(".", &self.dir).unwrap();
+            builder.finish().unwrap();
+            path
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+            let _ = std::fs::remove_file(self.dir.with_extension("tar"));
+        }
+    }
+
+    fn frame_index(image: &ImageBuffer) -> u8 {
+        image.get_pixel(0, 0)[2]
+    }
+
+    #[test]
+    fn parses_manifest_defaults() {
+        let manifest: Manifest =
+            serde_json::from_str(r#"{"frames": [{"file": "a.png"}]}"#).unwrap();
+        assert_eq!(
+            manifest,
+            Manifest {
+                screens: Vec::new(),
+                frames: vec![RecordedFrame {
+                    file: "a.png".to_string(),
+                    ..RecordedFrame::default()
+                }],
+            }
+        );
+        assert!(serde_json::from_str::<Manifest>(r#"{"screens": []}"#).is_err());
+    }
+
+    #[test]
+    fn sequential_mode_ends() {
+        let fixture = Fixture::new("sequential", [0, 0, 0]);
+        let provider = ReplayProvider::open(&fixture.dir).unwrap();
+        for i in 0..3 {
+            assert_eq!(provider.position(), i);
+            assert_eq!(frame_index(&provider.capture_full().unwrap()), i as u8);
+        }
+        assert!(matches!(
+            provider.capture_full(),
+            Err(Error::Backend { .. })
+        ));
+        provider.seek(1);
+        assert_eq!(frame_index(&provider.capture_full().unwrap()), 1);
+    }
+
+    #[test]
+    fn loop_mode_wraps() {
+        let fixture = Fixture::new("loop", [0, 0, 0]);
+        let provider = ReplayProvider::open(&fixture.dir)
+            .unwrap()
+            .with_mode(ReplayMode::Loop);
+        let played: Vec<u8> = (0..5)
+            .map(|_| frame_index(&provider.capture_full().unwrap()))
+            .collect();
+        assert_eq!(played, [0, 1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn realtime_mode_follows_timestamps() {
+        let fixture = Fixture::new("realtime", [1000, 1040, 1080]);
+        let provider = ReplayProvider::open(&fixture.dir)
+            .unwrap()
+            .with_mode(ReplayMode::Realtime);
+        assert_eq!(frame_index(&provider.capture_full().unwrap()), 0);
+        assert_eq!(frame_index(&provider.capture_full().unwrap()), 0);
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(provider.position(), 2);
+        assert_eq!(frame_index(&provider.capture_full().unwrap()), 2);
+        assert_eq!(frame_index(&provider.capture_full().unwrap()), 2);
+
+        provider.seek(1);
+        assert_eq!(frame_index(&provider.capture_full().unwrap()), 1);
+    }
+
+    #[test]
+    fn archive_matches_directory() {
+        let fixture = Fixture::new("archive", [0, 0, 0]);
+        let directory = ReplayProvider::open(&fixture.dir).unwrap();
+        let archive = ReplayProvider::open(fixture.archive()).unwrap();
+        assert_eq!(archive.manifest(), directory.manifest());
+        for _ in 0..3 {
+            assert_eq!(
+                archive.capture_full().unwrap(),
+                directory.capture_full().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn metadata_describes_the_captured_frame() {
+        let fixture = Fixture::new("metadata", [0, 0, 0]);
+        let provider = ReplayProvider::open(&fixture.dir).unwrap();
+        for i in 0..3 {
+            let screenshot = provider
+                .capture_with_metadata(&CaptureTarget::Window(7))
+                .unwrap();
+            let window = screenshot.metadata.window.unwrap();
+            assert_eq!(window.area, Area::new(-5 + i, 8, 4, 3));
+            assert_eq!(screenshot.metadata.area, window.area);
+            assert_eq!(
+                screenshot.image.get_pixel(0, 0).0,
+                [5 + i as u8, 3, i as u8, 255]
+            );
+        }
+    }
+
+    #[test]
+    fn crops_in_global_coordinates() {
+        let fixture = Fixture::new("crop", [0, 0, 0]);
+        let provider = ReplayProvider::open(&fixture.dir)
+            .unwrap()
+            .with_mode(ReplayMode::Loop);
+
+        let area = provider.capture_area(Area::new(-8, 6, 3, 2)).unwrap();
+        assert_eq!(area.dimensions(), (3, 2));
+        assert_eq!(area.get_pixel(0, 0).0, [2, 1, 0, 255]);
+        assert_eq!(area.get_pixel(2, 1).0, [4, 2, 0, 255]);
+
+        let window = provider.capture_focused_window().unwrap();
+        assert_eq!(window.dimensions(), (4, 3));
+        assert_eq!(window.get_pixel(0, 0).0, [6, 3, 1, 255]);
+
+        match provider.capture_area(Area::new(5, 5, 6, 1)) {
+            Err(Error::AreaOutOfBounds { bounds, .. }) => {
+                assert_eq!(bounds, Area::new(-10, 5, 20, 10))
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert!(matches!(
+            provider.capture_window(8),
+            Err(Error::WindowNotFound(8))
+        ));
+        // Extreme areas are reported instead of overflowing.
+        for area in [
+            Area::new(i64::MAX - 1, 6, 2, 1),
+            Area::new(-10, 5, u64::MAX, 1),
+        ] {
+            assert!(matches!(
+                provider.capture_area(area),
+                Err(Error::AreaOutOfBounds { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn windows_are_clipped_to_the_frame() {
+        let fixture = Fixture::new("clip", [0, 0, 0]);
+        let mut manifest = ReplayProvider::open(&fixture.dir).unwrap().manifest;
+        manifest.frames[0].windows[0].area = Area::new(-12, 13, 5, 5);
+        manifest.frames[1].windows[0].area = Area::new(100, 0, 5, 5);
+        let provider = ReplayProvider::from_manifest(&fixture.dir, manifest);
+
+        let screenshot = Capture::focused_window().run(&provider).unwrap();
+        assert_eq!(screenshot.metadata.area, Area::new(-10, 13, 3, 2));
+        assert_eq!(screenshot.metadata.kind, CaptureKind::Window);
+        assert_eq!(
+            screenshot.metadata.window.map(|window| window.area),
+            Some(Area::new(-12, 13, 5, 5))
+        );
+        assert_eq!(screenshot.image.get_pixel(0, 0).0, [0, 8, 0, 255]);
+
+        assert!(matches!(
+            provider.capture_focused_window(),
+            Err(Error::WindowNotViewable(7))
+        ));
+    }
+
+    #[test]
+    fn frame_files_stay_inside_the_recording() {
+        let fixture = Fixture::new("escape", [0, 0, 0]);
+        let mut manifest = ReplayProvider::open(&fixture.dir).unwrap().manifest;
+        let outside = fixture.dir.with_extension("png");
+        ImageBuffer::new(1, 1).save(&outside).unwrap();
+        manifest.frames[0].file = format!("../{}", outside.file_name().unwrap().to_string_lossy());
+        manifest.frames[1].file = outside.to_string_lossy().into_owned();
+        manifest.frames[2].file = "./frame-2.png".to_string();
+        let provider = ReplayProvider::from_manifest(&fixture.dir, manifest);
+        let results: Vec<bool> = (0..3)
+            .map(|index| {
+                provider.seek(index);
+                provider.capture_full().is_ok()
+            })
+            .collect();
+        let _ = std::fs::remove_file(&outside);
+        assert_eq!(results, [false, false, true]);
+    }
+
+    #[test]
+    fn unknown_screens_leave_the_position_unchanged() {
+        let fixture = Fixture::new("screens", [0, 0, 0]);
+        let mut manifest = ReplayProvider::open(&fixture.dir).unwrap().manifest;
+        manifest.screens = vec![Screen {
+            id: 3,
+            name: "screen".to_string(),
+            area: Area::new(-10, 5, 20, 10),
+            rotation: Rotation::Normal,
+            primary: true,
+            refresh_rate: None,
+            scale_factor: 1.0,
+        }];
+        let provider = ReplayProvider::from_manifest(&fixture.dir, manifest);
+
+        for _ in 0..2 {
+            assert!(matches!(
+                provider.capture_with_metadata(&CaptureTarget::Screen(4)),
+                Err(Error::ScreenNotFound(4))
+            ));
+            assert!(matches!(
+                provider.capture_screen(4),
+                Err(Error::ScreenNotFound(4))
+            ));
+        }
+        assert_eq!(provider.position(), 0);
+        let screenshot = provider
+            .capture_with_metadata(&CaptureTarget::CurrentScreen)
+            .unwrap();
+        assert_eq!(screenshot.metadata.screen_id, Some(3));
+        assert_eq!(frame_index(&screenshot.image), 0);
+        assert_eq!(provider.position(), 1);
+    }
+}
@@ -1,7 +1,7 @@
 use std::mem::size_of;
 
 use windows::Win32::{
-    Foundation::{GetLastError, ERROR_INVALID_PARAMETER, HWND, RECT},
+    Foundation::{ERROR_INVALID_PARAMETER, HWND, RECT},
     Graphics::Gdi::{
         CreateCompatibleBitmap, CreateCompatibleDC, CreatedHDC, DeleteDC, DeleteObject, GetDC,
         GetDIBits, ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
@@ -19,8 +19,8 @@ impl GdiHelper {
     unsafe fn get_dc(hwnd: HWND) -> Result<HDC> {
         let hdc = GetDC(hwnd);
         if hdc.is_invalid() {
-            eprintln!("GetDC failed: {:?}", GetLastError());
-            Err(Error::WindowCaptureFailed)
+            let error = windows::core::Error::from_win32();
+            Err(Error::backend("gdi", error))
         } else {
             Ok(hdc)
         }
@@ -29,9 +29,9 @@ impl GdiHelper {
     unsafe fn get_window_rect(hwnd: HWND, hdc: HDC) -> Result<RECT> {
         let mut rect = RECT::default();
         if !GetClientRect(hwnd, &mut rect).as_bool() {
-            eprintln!("GetClientRect failed: {:?}", GetLastError());
+            let error = windows::core::Error::from_win32();
             ReleaseDC(HWND::default(), hdc);
-            Err(Error::WindowCaptureFailed)
+            Err(Error::backend("gdi", error))
         } else {
             Ok(rect)
         }
@@ -40,9 +40,9 @@ impl GdiHelper {
     unsafe fn create_compatible_dc(hdc: HDC) -> Result<CreatedHDC> {
         let hdc = CreateCompatibleDC(hdc);
         if hdc.is_invalid() {
-            eprintln!("CreateCompatibleDC failed: {:?}", GetLastError());
+            let error = windows::core::Error::from_win32();
             ReleaseDC(HWND::default(), hdc);
-            Err(Error::WindowCaptureFailed)
+            Err(Error::backend("gdi", error))
         } else {
             Ok(hdc)
         }
@@ -56,10 +56,10 @@ impl GdiHelper {
     ) -> Result<HBITMAP> {
         let hbmp = CreateCompatibleBitmap(hdc, w, h);
         if hbmp.is_invalid() {
-            eprintln!("CreateCompatibleBitmap failed: {:?}", GetLastError());
+            let error = windows::core::Error::from_win32();
             DeleteDC(chdc);
             ReleaseDC(HWND::default(), hdc);
-            Err(Error::WindowCaptureFailed)
+            Err(Error::backend("gdi", error))
         } else {
             Ok(hbmp)
         }
@@ -68,11 +68,11 @@ impl GdiHelper {
     unsafe fn select_object(hdc: HDC, chdc: CreatedHDC, hbmp: HBITMAP) -> Result<HGDIOBJ> {
         let hgdiobj = SelectObject(chdc, hbmp);
         if hgdiobj.is_invalid() {
-            eprintln!("SelectObject failed: {:?}", GetLastError());
+            let error = windows::core::Error::from_win32();
             DeleteDC(chdc);
             DeleteObject(hbmp);
             ReleaseDC(HWND::default(), hdc);
-            Err(Error::WindowCaptureFailed)
+            Err(Error::backend("gdi", error))
         } else {
             Ok(hgdiobj)
         }
@@ -86,11 +86,11 @@ impl GdiHelper {
         flags: PRINT_WINDOW_FLAGS,
     ) -> Result<()> {
         if !PrintWindow(hwnd, chdc, flags).as_bool() {
-            eprintln!("PrintWindow failed: {:?}", GetLastError());
+            let error = windows::core::Error::from_win32();
             DeleteDC(chdc);
             DeleteObject(hbmp);
             ReleaseDC(HWND::default(), hdc);
-            Err(Error::WindowCaptureFailed)
+            Err(Error::backend("gdi", error))
         } else {
             Ok(())
         }
@@ -114,11 +114,11 @@ impl GdiHelper {
             DIB_RGB_COLORS,
         );
         if dib == 0 || dib == ERROR_INVALID_PARAMETER.0 as i32 {
-            eprintln!("GetDIBits failed: {:?}", GetLastError());
+            let error = windows::core::Error::from_win32();
             DeleteDC(chdc);
             DeleteObject(hbmp);
             ReleaseDC(HWND::default(), hdc);
-            Err(Error::WindowCaptureFailed)
+            Err(Error::backend("gdi", error))
         } else {
             Ok(())
         }
@@ -180,7 +180,10 @@ impl WindowCaptureProvider for GdiProvider {
             DeleteDC(chdc);
             DeleteObject(hbmp);
             ReleaseDC(HWND::default(), hdc);
-            ImageBuffer::from_raw(w as u32, h as u32, buf).ok_or(Error::WindowCaptureFailed)
+            ImageBuffer::from_raw(w as u32, h as u32, buf).ok_or(Error::InvalidImageData {
+                width: w as u32,
+                height: h as u32,
+            })
         }
     }
 
@@ -194,7 +197,7 @@ impl WindowCaptureProvider for GdiProvider {
 
 impl ScreenCaptureProvider for GdiProvider {
     fn capture_screen(&self, _screen_id: ScreenId) -> Result<ImageBuffer> {
-        unimplemented!()
+        Err(Error::Unsupported(Capability::ScreenCapture))
         // unsafe {
         //     let hdc = GdiHelper::get_dc(HWND::default())?;
         //     let chdc = GdiHelper::create_compatible_dc(hdc)?;
@@ -202,18 +205,52 @@ impl ScreenCaptureProvider for GdiProvider {
     }
 
     fn capture_current_screen(&self) -> Result<ImageBuffer> {
-        unimplemented!()
+        Err(Error::Unsupported(Capability::ScreenCapture))
     }
 }
 
 impl AreaCaptureProvider for GdiProvider {
     fn capture_area(&self, _area: Area) -> Result<ImageBuffer> {
-        unimplemented!()
+        Err(Error::Unsupported(Capability::AreaCapture))
     }
 }
 
 impl FullCaptureProvider for GdiProvider {
     fn capture_full(&self) -> Result<ImageBuffer> {
-        unimplemented!()
+        Err(Error::Unsupported(Capability::FullCapture))
+    }
+}
+
+impl WindowEnumerationProvider for GdiProvider {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>> {
+        Err(Error::Unsupported(Capability::WindowEnumeration))
+    }
+}
+
+impl ScreenEnumerationProvider for GdiProvider {
+    fn list_screens(&self) -> Result<Vec<Screen>> {
+        Err(Error::Unsupported(Capability::ScreenEnumeration))
+    }
+}
+
+impl CaptureBackend for GdiProvider {
+    fn name(&self) -> &'static str {
+        "gdi"
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            window_capture: true,
+            screen_capture: false,
+            area_capture: false,
+            full_capture: false,
+            window_enumeration: false,
+            screen_enumeration: false,
+            cursor: false,
+            decorations: false,
+            alpha: false,
+            streaming: false,
+            multi_monitor: false,
+        }
     }
 }
@@ -17,32 +17,67 @@ impl GraphicsCaptureProvider {
 
 impl WindowCaptureProvider for GraphicsCaptureProvider {
     fn capture_window(&self, _window_id: WindowId) -> Result<ImageBuffer> {
-        unimplemented!()
+        Err(Error::Unsupported(Capability::WindowCapture))
     }
 
     fn capture_focused_window(&self) -> Result<ImageBuffer> {
-        unimplemented!()
+        Err(Error::Unsupported(Capability::WindowCapture))
     }
 }
 
 impl ScreenCaptureProvider for GraphicsCaptureProvider {
     fn capture_screen(&self, _screen_id: ScreenId) -> Result<ImageBuffer> {
-        unimplemented!()
+        Err(Error::Unsupported(Capability::ScreenCapture))
     }
 
     fn capture_current_screen(&self) -> Result<ImageBuffer> {
-        unimplemented!()
+        Err(Error::Unsupported(Capability::ScreenCapture))
     }
 }
 
 impl AreaCaptureProvider for GraphicsCaptureProvider {
     fn capture_area(&self, _area: Area) -> Result<ImageBuffer> {
-        unimplemented!()
+        Err(Error::Unsupported(Capability::AreaCapture))
     }
 }
 
 impl FullCaptureProvider for GraphicsCaptureProvider {
     fn capture_full(&self) -> Result<ImageBuffer> {
-        unimplemented!()
+        Err(Error::Unsupported(Capability::FullCapture))
+    }
+}
+
+impl WindowEnumerationProvider for GraphicsCaptureProvider {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>> {
+        Err(Error::Unsupported(Capability::WindowEnumeration))
+    }
+}
+
+impl ScreenEnumerationProvider for GraphicsCaptureProvider {
+    fn list_screens(&self) -> Result<Vec<Screen>> {
+        Err(Error::Unsupported(Capability::ScreenEnumeration))
+    }
+}
+
+/// Placeholder until Windows.Graphics.Capture is wired up; nothing is supported yet.
+impl CaptureBackend for GraphicsCaptureProvider {
+    fn name(&self) -> &'static str {
+        "graphics_capture"
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            window_capture: false,
+            screen_capture: false,
+            area_capture: false,
+            full_capture: false,
+            window_enumeration: false,
+            screen_enumeration: false,
+            cursor: false,
+            decorations: false,
+            alpha: false,
+            streaming: false,
+            multi_monitor: false,
+        }
     }
 }
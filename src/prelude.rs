@@ -1 +1,2 @@
+pub use crate::error::Error;
 pub use crate::traits::*;
@@ -1,9 +1,35 @@
 mod area;
+mod capabilities;
+mod capability;
+mod capture_metadata;
+mod capture_options;
+mod capture_target;
+mod cursor_image;
+mod desktop_capture;
 mod generic_area;
+mod pixel_format;
+mod raw_frame;
+mod screen;
 mod screen_id;
+mod screenshot;
+mod stream_id;
 mod window_id;
+mod window_info;
 
 pub use self::area::Area;
+pub use self::capabilities::Capabilities;
+pub use self::capability::Capability;
+pub use self::capture_metadata::{CaptureKind, CaptureMetadata};
+pub use self::capture_options::CaptureOptions;
+pub use self::capture_target::CaptureTarget;
+pub use self::cursor_image::CursorImage;
+pub use self::desktop_capture::DesktopCapture;
 pub use self::generic_area::GenericArea;
+pub use self::pixel_format::PixelFormat;
+pub use self::raw_frame::RawFrame;
+pub use self::screen::{Rotation, Screen};
 pub use self::screen_id::ScreenId;
+pub use self::screenshot::Screenshot;
+pub use self::stream_id::StreamId;
 pub use self::window_id::WindowId;
+pub use self::window_info::WindowInfo;
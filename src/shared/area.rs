@@ -1,3 +1,5 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Area {
     pub x: i64,
     pub y: i64,
@@ -14,4 +16,71 @@ impl Area {
             height,
         }
     }
+
+    /// The x coordinate just past the right edge, saturating at `i64::MAX`.
+    pub fn right(&self) -> i64 {
+        (self.x as i128 + self.width as i128).min(i64::MAX as i128) as i64
+    }
+
+    /// The y coordinate just past the bottom edge, saturating at `i64::MAX`.
+    pub fn bottom(&self) -> i64 {
+        (self.y as i128 + self.height as i128).min(i64::MAX as i128) as i64
+    }
+
+    /// The overlap of both areas, if any.
+    pub fn intersect(&self, other: &Area) -> Option<Area> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        (right > x && bottom > y).then(|| Area::new(x, y, right.abs_diff(x), bottom.abs_diff(y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersect() {
+        let area = Area::new(-10, 0, 20, 10);
+        assert_eq!(
+            area.intersect(&Area::new(5, 5, 100, 100)),
+            Some(Area::new(5, 5, 5, 5))
+        );
+        assert_eq!(
+            area.intersect(&Area::new(-5, 2, 2, 2)),
+            Some(Area::new(-5, 2, 2, 2))
+        );
+        // Touching edges do not overlap.
+        assert_eq!(area.intersect(&Area::new(10, 0, 5, 5)), None);
+        assert_eq!(area.intersect(&Area::new(0, -5, 5, 5)), None);
+        assert_eq!(area.intersect(&Area::new(0, 0, 0, 5)), None);
+    }
+
+    #[test]
+    fn extreme_areas_saturate() {
+        let huge = Area::new(i64::MIN, i64::MAX - 1, u64::MAX, u64::MAX);
+        assert_eq!(huge.right(), i64::MAX);
+        assert_eq!(huge.bottom(), i64::MAX);
+        let area = Area::new(-10, 0, 20, 10);
+        assert_eq!(huge.intersect(&area), None);
+        assert_eq!(
+            Area::new(i64::MIN, -5, u64::MAX, 100).intersect(&area),
+            Some(Area::new(-10, 0, 20, 10))
+        );
+        assert_eq!(
+            Area::new(i64::MIN, i64::MIN, u64::MAX, u64::MAX).intersect(&Area::new(
+                i64::MAX - 1,
+                i64::MAX - 1,
+                10,
+                10
+            )),
+            Some(Area::new(i64::MAX - 1, i64::MAX - 1, 1, 1))
+        );
+        assert_eq!(
+            Area::new(i64::MIN, 0, u64::MAX, 1).intersect(&Area::new(i64::MIN, 0, u64::MAX, 1)),
+            Some(Area::new(i64::MIN, 0, u64::MAX, 1))
+        );
+    }
 }
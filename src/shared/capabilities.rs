@@ -0,0 +1,53 @@
+use super::Capability;
+
+/// The set of operations a backend supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities {
+    /// Capturing individual windows.
+    pub window_capture: bool,
+    /// Capturing a single monitor.
+    pub screen_capture: bool,
+    /// Capturing a rectangle given in desktop coordinates.
+    pub area_capture: bool,
+    /// Capturing all monitors at once.
+    pub full_capture: bool,
+    /// Listing the top-level windows.
+    pub window_enumeration: bool,
+    /// Listing the monitors and their layout.
+    pub screen_enumeration: bool,
+    /// Including the mouse pointer in captures.
+    pub cursor: bool,
+    /// Including window-manager decorations in window captures on request.
+    pub decorations: bool,
+    /// Producing images with a meaningful alpha channel instead of opaque pixels.
+    pub alpha: bool,
+    /// Efficient repeated capture of the same target, e.g. by reusing buffers or tracking damage.
+    pub streaming: bool,
+    /// Addressing the individual monitors of a multi-monitor setup.
+    pub multi_monitor: bool,
+}
+
+impl Capabilities {
+    pub fn supports(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::WindowCapture => self.window_capture,
+            Capability::ScreenCapture => self.screen_capture,
+            Capability::AreaCapture => self.area_capture,
+            Capability::FullCapture => self.full_capture,
+            Capability::WindowEnumeration => self.window_enumeration,
+            Capability::ScreenEnumeration => self.screen_enumeration,
+            Capability::Cursor => self.cursor,
+            Capability::Decorations => self.decorations,
+            Capability::Alpha => self.alpha,
+            Capability::Streaming => self.streaming,
+            Capability::MultiMonitor => self.multi_monitor,
+        }
+    }
+
+    /// The supported capabilities, in the order of [`Capability::ALL`].
+    pub fn iter(&self) -> impl Iterator<Item = Capability> + '_ {
+        Capability::ALL
+            .into_iter()
+            .filter(|capability| self.supports(*capability))
+    }
+}
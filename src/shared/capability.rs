@@ -0,0 +1,56 @@
+/// A single operation a provider may or may not support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Capability {
+    WindowCapture,
+    ScreenCapture,
+    AreaCapture,
+    FullCapture,
+    WindowEnumeration,
+    ScreenEnumeration,
+    /// Including the mouse pointer in captures.
+    Cursor,
+    /// Including window-manager decorations in window captures.
+    Decorations,
+    /// Producing images with a meaningful alpha channel.
+    Alpha,
+    /// Efficient repeated capture of the same target.
+    Streaming,
+    /// Addressing individual monitors of a multi-monitor setup.
+    MultiMonitor,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 11] = [
+        Capability::WindowCapture,
+        Capability::ScreenCapture,
+        Capability::AreaCapture,
+        Capability::FullCapture,
+        Capability::WindowEnumeration,
+        Capability::ScreenEnumeration,
+        Capability::Cursor,
+        Capability::Decorations,
+        Capability::Alpha,
+        Capability::Streaming,
+        Capability::MultiMonitor,
+    ];
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Capability::WindowCapture => "Window capture",
+            Capability::ScreenCapture => "Screen capture",
+            Capability::AreaCapture => "Area capture",
+            Capability::FullCapture => "Full capture",
+            Capability::WindowEnumeration => "Window enumeration",
+            Capability::ScreenEnumeration => "Screen enumeration",
+            Capability::Cursor => "Cursor",
+            Capability::Decorations => "Decorations",
+            Capability::Alpha => "Alpha",
+            Capability::Streaming => "Streaming",
+            Capability::MultiMonitor => "Multi-monitor",
+        };
+        f.write_str(name)
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::SystemTime;
+
+use super::{Area, Screen, ScreenId, WindowInfo};
+
+/// What kind of source a capture was taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CaptureKind {
+    Window,
+    Screen,
+    Area,
+    Full,
+}
+
+/// Information about a capture beyond its pixels.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CaptureMetadata {
+    pub kind: CaptureKind,
+    /// Position and size of the captured pixels in global desktop coordinates.
+    pub area: Area,
+    /// The captured window, for window captures.
+    pub window: Option<WindowInfo>,
+    /// The monitor showing the capture, if it is on a single one.
+    pub screen_id: Option<ScreenId>,
+    pub scale_factor: f64,
+    /// Name of the backend that took the capture, e.g. `"x11"`.
+    pub backend: String,
+    /// When the capture was started.
+    pub timestamp: SystemTime,
+}
+
+impl CaptureMetadata {
+    /// Describes a capture of `area`. Unless it is a full capture, the monitor and scale factor
+    /// are taken from the screen containing the centre of `area`.
+    pub fn new(
+        kind: CaptureKind,
+        area: Area,
+        window: Option<WindowInfo>,
+        screens: &[Screen],
+        backend: &str,
+        timestamp: SystemTime,
+    ) -> Self {
+        let (center_x, center_y) = (
+            area.x + (area.width / 2) as i64,
+            area.y + (area.height / 2) as i64,
+        );
+        let screen = match kind {
+            CaptureKind::Full => None,
+            _ => screens.iter().find(|screen| {
+                let screen = screen.area;
+                (screen.x..screen.x + screen.width as i64).contains(&center_x)
+                    && (screen.y..screen.y + screen.height as i64).contains(&center_y)
+            }),
+        };
+        CaptureMetadata {
+            kind,
+            area,
+            window,
+            screen_id: screen.map(|screen| screen.id),
+            scale_factor: screen.map_or(1.0, |screen| screen.scale_factor),
+            backend: backend.to_string(),
+            timestamp,
+        }
+    }
+}
@@ -0,0 +1,12 @@
+/// Per-capture options for [`CaptureBackend::capture_with_options`](crate::CaptureBackend).
+///
+/// Options only ever add to what the backend was configured with; a backend set up to draw the
+/// pointer keeps drawing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CaptureOptions {
+    /// Draws the mouse pointer into the capture.
+    pub cursor: bool,
+    /// Includes window-manager decorations in window captures.
+    pub decorations: bool,
+}
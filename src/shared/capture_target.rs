@@ -0,0 +1,40 @@
+use super::{Area, Capability, CaptureKind, ScreenId, WindowId};
+
+/// What to capture, for APIs that take the target as a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CaptureTarget {
+    Window(WindowId),
+    FocusedWindow,
+    Screen(ScreenId),
+    CurrentScreen,
+    Area(Area),
+    Full,
+    /// The topmost window below the mouse pointer.
+    WindowUnderCursor,
+}
+
+impl CaptureTarget {
+    /// The capability a backend needs to capture this target.
+    pub fn capability(&self) -> Capability {
+        match self {
+            CaptureTarget::Window(_)
+            | CaptureTarget::FocusedWindow
+            | CaptureTarget::WindowUnderCursor => Capability::WindowCapture,
+            CaptureTarget::Screen(_) | CaptureTarget::CurrentScreen => Capability::ScreenCapture,
+            CaptureTarget::Area(_) => Capability::AreaCapture,
+            CaptureTarget::Full => Capability::FullCapture,
+        }
+    }
+
+    pub fn kind(&self) -> CaptureKind {
+        match self {
+            CaptureTarget::Window(_)
+            | CaptureTarget::FocusedWindow
+            | CaptureTarget::WindowUnderCursor => CaptureKind::Window,
+            CaptureTarget::Screen(_) | CaptureTarget::CurrentScreen => CaptureKind::Screen,
+            CaptureTarget::Area(_) => CaptureKind::Area,
+            CaptureTarget::Full => CaptureKind::Full,
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use super::Area;
+use crate::ImageBuffer;
+
+/// The mouse pointer as currently shown on screen.
+#[derive(Debug, Clone)]
+pub struct CursorImage {
+    /// Cursor pixels with straight (not premultiplied) alpha.
+    pub image: ImageBuffer,
+    /// Pointer position in desktop coordinates.
+    pub x: i64,
+    pub y: i64,
+    /// Position of the pointer within `image`.
+    pub hotspot_x: u32,
+    pub hotspot_y: u32,
+}
+
+impl CursorImage {
+    /// Area covered by the cursor image, in desktop coordinates.
+    pub fn area(&self) -> Area {
+        Area::new(
+            self.x - self.hotspot_x as i64,
+            self.y - self.hotspot_y as i64,
+            self.image.width() as u64,
+            self.image.height() as u64,
+        )
+    }
+
+    /// Blends the cursor onto `image`, whose top-left corner is at (`origin_x`, `origin_y`) in
+    /// desktop coordinates.
+    pub fn draw_onto(&self, image: &mut ImageBuffer, origin_x: i64, origin_y: i64) {
+        let area = self.area();
+        for (cursor_x, cursor_y, source) in self.image.enumerate_pixels() {
+            let x = area.x + cursor_x as i64 - origin_x;
+            let y = area.y + cursor_y as i64 - origin_y;
+            let alpha = source[3] as u32;
+            if alpha == 0
+                || x < 0
+                || y < 0
+                || x >= image.width() as i64
+                || y >= image.height() as i64
+            {
+                continue;
+            }
+            // "Over" in straight alpha, with the result alpha scaled by 255. It is never zero, since
+            // transparent cursor pixels were skipped.
+            let target = image.get_pixel_mut(x as u32, y as u32);
+            let below = target[3] as u32 * (255 - alpha);
+            let out_alpha = alpha * 255 + below;
+            for channel in 0..3 {
+                let color = source[channel] as u32 * alpha * 255 + target[channel] as u32 * below;
+                target[channel] = ((color + out_alpha / 2) / out_alpha) as u8;
+            }
+            target[3] = ((out_alpha + 127) / 255) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn draw_onto_blends_and_clips() {
+        // Opaque, half transparent and fully transparent pixels, with the hotspot in the middle.
+        let cursor = CursorImage {
+            image: ImageBuffer::from_vec(
+                3,
+                1,
+                vec![255, 255, 255, 255, 255, 0, 0, 128, 0, 0, 255, 0],
+            )
+            .unwrap(),
+            x: 11,
+            y: 20,
+            hotspot_x: 1,
+            hotspot_y: 0,
+        };
+        assert_eq!(cursor.area(), Area::new(10, 20, 3, 1));
+
+        let background = Rgba([0, 0, 0, 255]);
+        let mut image = ImageBuffer::from_pixel(2, 2, background);
+        cursor.draw_onto(&mut image, 9, 19);
+        assert_eq!(*image.get_pixel(0, 1), background);
+        assert_eq!(*image.get_pixel(1, 1), Rgba([255, 255, 255, 255]));
+        assert_eq!(*image.get_pixel(1, 0), background);
+
+        let mut image = ImageBuffer::from_pixel(3, 1, Rgba([0, 0, 0, 0]));
+        cursor.draw_onto(&mut image, 10, 20);
+        assert_eq!(*image.get_pixel(1, 0), Rgba([255, 0, 0, 128]));
+        assert_eq!(*image.get_pixel(2, 0), Rgba([0, 0, 0, 0]));
+
+        let mut image = ImageBuffer::from_pixel(3, 1, Rgba([0, 0, 255, 128]));
+        cursor.draw_onto(&mut image, 10, 20);
+        assert_eq!(*image.get_pixel(1, 0), Rgba([170, 0, 85, 192]));
+
+        // Entirely outside of the image.
+        let mut image = ImageBuffer::from_pixel(2, 2, background);
+        cursor.draw_onto(&mut image, 100, -100);
+        assert!(image.pixels().all(|pixel| *pixel == background));
+    }
+}
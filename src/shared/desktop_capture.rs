@@ -0,0 +1,93 @@
+use image::imageops;
+
+use super::{Area, Screen};
+use crate::ImageBuffer;
+
+/// A capture of the whole desktop together with the monitor layout.
+#[derive(Debug, Clone)]
+pub struct DesktopCapture {
+    pub image: ImageBuffer,
+    /// Desktop area covered by `image`, whose top-left corner is the image origin.
+    pub bounds: Area,
+    /// Monitors in desktop coordinates. Pixels outside of all monitors hold a background colour.
+    pub screens: Vec<Screen>,
+}
+
+impl DesktopCapture {
+    /// Area of `screen` in image coordinates.
+    pub fn image_area(&self, screen: &Screen) -> Area {
+        Area::new(
+            screen.area.x - self.bounds.x,
+            screen.area.y - self.bounds.y,
+            screen.area.width,
+            screen.area.height,
+        )
+    }
+
+    /// Crops one image per monitor, in the order of `screens`.
+    pub fn split(&self) -> Vec<(Screen, ImageBuffer)> {
+        let image_bounds = Area::new(0, 0, self.image.width() as u64, self.image.height() as u64);
+        self.screens
+            .iter()
+            .filter_map(|screen| {
+                let area = self.image_area(screen).intersect(&image_bounds)?;
+                let image = imageops::crop_imm(
+                    &self.image,
+                    area.x as u32,
+                    area.y as u32,
+                    area.width as u32,
+                    area.height as u32,
+                )
+                .to_image();
+                Some((screen.clone(), image))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::{Rotation, ScreenId};
+    use image::Rgba;
+
+    fn screen(id: ScreenId, area: Area) -> Screen {
+        Screen {
+            id,
+            name: format!("S{id}"),
+            area,
+            rotation: Rotation::Normal,
+            primary: false,
+            refresh_rate: None,
+            scale_factor: 1.0,
+        }
+    }
+
+    #[test]
+    fn split_crops_each_screen() {
+        let capture = DesktopCapture {
+            image: ImageBuffer::from_fn(30, 20, |x, y| Rgba([x as u8, y as u8, 0, 255])),
+            bounds: Area::new(-10, 0, 30, 20),
+            screens: vec![
+                screen(0, Area::new(0, 0, 20, 10)),
+                screen(1, Area::new(-10, 10, 10, 10)),
+                // Partly and entirely outside of the image.
+                screen(2, Area::new(15, 15, 10, 10)),
+                screen(3, Area::new(100, 0, 10, 10)),
+            ],
+        };
+        let split = capture.split();
+        let layout: Vec<_> = split
+            .iter()
+            .map(|(screen, image)| (screen.id, image.dimensions(), image.get_pixel(0, 0).0))
+            .collect();
+        assert_eq!(
+            layout,
+            [
+                (0, (20, 10), [10, 0, 0, 255]),
+                (1, (10, 10), [0, 10, 0, 255]),
+                (2, (5, 5), [25, 15, 0, 255]),
+            ]
+        );
+    }
+}
@@ -21,10 +21,10 @@ where
 
     fn try_from(area: Area) -> Result<Self, Self::Error> {
         Ok(GenericArea {
-            x: XY::try_from(area.x).map_err(|e| Error::AreaIntConversionError(e))?,
-            y: XY::try_from(area.y).map_err(|e| Error::AreaIntConversionError(e))?,
-            width: WH::try_from(area.width).map_err(|e| Error::AreaIntConversionError(e))?,
-            height: WH::try_from(area.height).map_err(|e| Error::AreaIntConversionError(e))?,
+            x: XY::try_from(area.x).map_err(Error::AreaIntConversionError)?,
+            y: XY::try_from(area.y).map_err(Error::AreaIntConversionError)?,
+            width: WH::try_from(area.width).map_err(Error::AreaIntConversionError)?,
+            height: WH::try_from(area.height).map_err(Error::AreaIntConversionError)?,
         })
     }
 }
@@ -0,0 +1,67 @@
+/// Byte layout of the pixels in a [`RawFrame`](super::RawFrame).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PixelFormat {
+    pub bytes_per_pixel: u8,
+    /// Byte offsets of the colour channels within a pixel.
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    /// Byte offset of the alpha channel, or `None` if the pixels are opaque.
+    pub alpha: Option<u8>,
+}
+
+impl PixelFormat {
+    /// Blue, green, red and an unused byte, the native layout of most X servers and of Windows.
+    pub const BGRX8888: PixelFormat = PixelFormat::new(4, 2, 1, 0, None);
+    pub const BGRA8888: PixelFormat = PixelFormat::new(4, 2, 1, 0, Some(3));
+    pub const RGBX8888: PixelFormat = PixelFormat::new(4, 0, 1, 2, None);
+    /// The layout of [`ImageBuffer`](crate::ImageBuffer).
+    pub const RGBA8888: PixelFormat = PixelFormat::new(4, 0, 1, 2, Some(3));
+    pub const BGR888: PixelFormat = PixelFormat::new(3, 2, 1, 0, None);
+    pub const RGB888: PixelFormat = PixelFormat::new(3, 0, 1, 2, None);
+
+    pub const fn new(bytes_per_pixel: u8, red: u8, green: u8, blue: u8, alpha: Option<u8>) -> Self {
+        PixelFormat {
+            bytes_per_pixel,
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// Whether all channels lie within a pixel.
+    pub fn is_valid(&self) -> bool {
+        let inside = |offset: u8| offset < self.bytes_per_pixel;
+        inside(self.red)
+            && inside(self.green)
+            && inside(self.blue)
+            && self.alpha.into_iter().all(inside)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validation() {
+        let cases = [
+            (PixelFormat::BGRX8888, true),
+            (PixelFormat::BGRA8888, true),
+            (PixelFormat::RGBX8888, true),
+            (PixelFormat::RGBA8888, true),
+            (PixelFormat::BGR888, true),
+            (PixelFormat::RGB888, true),
+            (PixelFormat::new(2, 0, 1, 1, None), true),
+            (PixelFormat::new(3, 0, 1, 2, Some(3)), false),
+            (PixelFormat::new(3, 3, 1, 0, None), false),
+            (PixelFormat::new(4, 0, 4, 2, None), false),
+            (PixelFormat::new(0, 0, 0, 0, None), false),
+        ];
+        for (format, valid) in cases {
+            assert_eq!(format.is_valid(), valid, "{format:?}");
+        }
+    }
+}
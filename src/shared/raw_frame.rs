@@ -0,0 +1,326 @@
+use std::borrow::Cow;
+
+use super::PixelFormat;
+use crate::{
+    error::{Error, Result},
+    ImageBuffer,
+};
+
+/// Pixels in the layout the backend produced them, either borrowed from the backend's buffer or
+/// owned.
+///
+/// Converting to an [`ImageBuffer`] is an explicit step, for callers such as encoders that can
+/// consume the native layout directly.
+#[derive(Debug, Clone)]
+pub struct RawFrame<'a> {
+    width: u32,
+    height: u32,
+    stride: usize,
+    format: PixelFormat,
+    data: Cow<'a, [u8]>,
+}
+
+impl<'a> RawFrame<'a> {
+    /// Wraps `data`, whose rows of `width` pixels start `stride` bytes apart.
+    pub fn new(
+        width: u32,
+        height: u32,
+        stride: usize,
+        format: PixelFormat,
+        data: impl Into<Cow<'a, [u8]>>,
+    ) -> Result<Self> {
+        let data = data.into();
+        let row = width as usize * format.bytes_per_pixel as usize;
+        let required = match height {
+            0 => 0,
+            height => stride * (height as usize - 1) + row,
+        };
+        if !format.is_valid() || stride < row || data.len() < required {
+            return Err(Error::InvalidImageData { width, height });
+        }
+        Ok(RawFrame {
+            width,
+            height,
+            stride,
+            format,
+            data,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Distance between the starts of two rows, in bytes. Rows may be padded.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The pixels of row `y`, without padding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y` is out of bounds.
+    pub fn row(&self, y: u32) -> &[u8] {
+        assert!(y < self.height, "row {y} out of bounds");
+        let start = y as usize * self.stride;
+        &self.data[start..start + self.width as usize * self.format.bytes_per_pixel as usize]
+    }
+
+    /// Whether the pixels are borrowed from the backend.
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.data, Cow::Borrowed(_))
+    }
+
+    /// Copies borrowed pixels, so the frame can outlive the backend's buffer.
+    pub fn into_owned(self) -> RawFrame<'static> {
+        RawFrame {
+            data: Cow::Owned(self.data.into_owned()),
+            ..self
+        }
+    }
+
+    pub fn into_data(self) -> Cow<'a, [u8]> {
+        self.data
+    }
+
+    /// Converts the pixels to RGBA.
+    pub fn to_image(&self) -> ImageBuffer {
+        let mut image = ImageBuffer::new(self.width, self.height);
+        self.copy_rows(&mut image, 0, 0);
+        image
+    }
+
+    /// Converts the pixels to `format`, in packed rows. Bytes that hold no channel are zero.
+    pub fn convert(&self, format: PixelFormat) -> Result<RawFrame<'static>> {
+        if format == self.format
+            && self.stride == self.width as usize * format.bytes_per_pixel as usize
+        {
+            return Ok(self.clone().into_owned());
+        }
+        let bytes_per_pixel = format.bytes_per_pixel as usize;
+        let stride = self.width as usize * bytes_per_pixel;
+        if !format.is_valid() {
+            return Err(Error::InvalidImageData {
+                width: self.width,
+                height: self.height,
+            });
+        }
+        let source_format = self.format;
+        let mut data = vec![0; stride * self.height as usize];
+        for (y, target) in (0..self.height).zip(data.chunks_exact_mut(stride.max(1))) {
+            let pixels = self
+                .row(y)
+                .chunks_exact(source_format.bytes_per_pixel as usize);
+            for (pixel, converted) in pixels.zip(target.chunks_exact_mut(bytes_per_pixel)) {
+                converted[format.red as usize] = pixel[source_format.red as usize];
+                converted[format.green as usize] = pixel[source_format.green as usize];
+                converted[format.blue as usize] = pixel[source_format.blue as usize];
+                if let Some(alpha) = format.alpha {
+                    converted[alpha as usize] = source_format
+                        .alpha
+                        .map_or(0xFF, |alpha| pixel[alpha as usize]);
+                }
+            }
+        }
+        RawFrame::new(self.width, self.height, stride, format, data)
+    }
+
+    /// Converts the pixels into the rectangle of `image` starting at (`x`, `y`).
+    pub fn copy_to(&self, image: &mut ImageBuffer, x: u32, y: u32) -> Result<()> {
+        if x as u64 + self.width as u64 > image.width() as u64
+            || y as u64 + self.height as u64 > image.height() as u64
+        {
+            return Err(Error::InvalidImageData {
+                width: self.width,
+                height: self.height,
+            });
+        }
+        self.copy_rows(image, x, y);
+        Ok(())
+    }
+
+    fn copy_rows(&self, image: &mut ImageBuffer, x: u32, y: u32) {
+        let format = self.format;
+        let image_stride = image.width() as usize * 4;
+        let target: &mut [u8] = image;
+        for row_index in 0..self.height {
+            let start = (y + row_index) as usize * image_stride + x as usize * 4;
+            let target = &mut target[start..start + self.width as usize * 4];
+            let source = self.row(row_index);
+            if format == PixelFormat::RGBA8888 {
+                target.copy_from_slice(source);
+                continue;
+            }
+            let pixels = source.chunks_exact(format.bytes_per_pixel as usize);
+            for (pixel, rgba) in pixels.zip(target.chunks_exact_mut(4)) {
+                rgba[0] = pixel[format.red as usize];
+                rgba[1] = pixel[format.green as usize];
+                rgba[2] = pixel[format.blue as usize];
+                rgba[3] = format.alpha.map_or(0xFF, |alpha| pixel[alpha as usize]);
+            }
+        }
+    }
+}
+
+/// Hands the image's buffer over without copying.
+impl From<ImageBuffer> for RawFrame<'static> {
+    fn from(image: ImageBuffer) -> Self {
+        let (width, height) = image.dimensions();
+        RawFrame {
+            width,
+            height,
+            stride: width as usize * 4,
+            format: PixelFormat::RGBA8888,
+            data: Cow::Owned(image.into_raw()),
+        }
+    }
+}
+
+impl<'a> From<&'a ImageBuffer> for RawFrame<'a> {
+    fn from(image: &'a ImageBuffer) -> Self {
+        let (width, height) = image.dimensions();
+        RawFrame {
+            width,
+            height,
+            stride: width as usize * 4,
+            format: PixelFormat::RGBA8888,
+            data: Cow::Borrowed(image.as_raw()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The pixels of every test frame, as RGBA.
+    const PIXELS: [[u8; 4]; 4] = [
+        [1, 2, 3, 255],
+        [4, 5, 6, 255],
+        [7, 8, 9, 255],
+        [10, 11, 12, 255],
+    ];
+
+    /// Lays out the 2x2 test pixels in `format` with `stride`, padding with 0xEE.
+    fn encode(format: PixelFormat, stride: usize, alpha: u8) -> Vec<u8> {
+        let mut data = vec![0xEE; stride * 2];
+        for (i, [r, g, b, _]) in PIXELS.into_iter().enumerate() {
+            let start = (i / 2) * stride + (i % 2) * format.bytes_per_pixel as usize;
+            let pixel = &mut data[start..start + format.bytes_per_pixel as usize];
+            pixel[format.red as usize] = r;
+            pixel[format.green as usize] = g;
+            pixel[format.blue as usize] = b;
+            if let Some(offset) = format.alpha {
+                pixel[offset as usize] = alpha;
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn converts_to_rgba() {
+        let cases = [
+            (PixelFormat::BGRX8888, 8, 255),
+            (PixelFormat::BGRA8888, 8, 128),
+            (PixelFormat::RGBA8888, 8, 128),
+            (PixelFormat::RGBX8888, 8, 255),
+            (PixelFormat::BGR888, 6, 255),
+            (PixelFormat::RGB888, 6, 255),
+            // Rows padded to a multiple of 4 and 16 bytes.
+            (PixelFormat::BGR888, 8, 255),
+            (PixelFormat::BGRX8888, 16, 255),
+            (PixelFormat::RGBA8888, 12, 7),
+        ];
+        for (format, stride, alpha) in cases {
+            let data = encode(format, stride, alpha);
+            let frame = RawFrame::new(2, 2, stride, format, &data[..]).unwrap();
+            let expected: Vec<u8> = PIXELS
+                .into_iter()
+                .flat_map(|[r, g, b, _]| [r, g, b, alpha])
+                .collect();
+            assert_eq!(frame.to_image().into_raw(), expected, "{format:?} {stride}");
+
+            let mut image = ImageBuffer::new(3, 3);
+            frame.copy_to(&mut image, 1, 1).unwrap();
+            assert_eq!(image.get_pixel(0, 0).0, [0; 4]);
+            assert_eq!(
+                image.get_pixel(2, 2).0[..3],
+                [10, 11, 12],
+                "{format:?} {stride}"
+            );
+            assert!(frame.copy_to(&mut image, 2, 0).is_err());
+        }
+    }
+
+    #[test]
+    fn converts_between_formats() {
+        let source = RawFrame::new(
+            2,
+            2,
+            16,
+            PixelFormat::BGRX8888,
+            encode(PixelFormat::BGRX8888, 16, 0),
+        )
+        .unwrap();
+        let cases = [
+            (PixelFormat::BGRX8888, 8),
+            (PixelFormat::RGBA8888, 8),
+            (PixelFormat::BGR888, 6),
+            (PixelFormat::new(5, 4, 2, 0, Some(1)), 10),
+        ];
+        for (format, stride) in cases {
+            let converted = source.convert(format).unwrap();
+            assert_eq!(converted.format(), format);
+            assert_eq!(converted.stride(), stride);
+            // Opaque source pixels get an opaque alpha channel; unused bytes are zero.
+            let mut expected = encode(format, stride, 255);
+            if format == PixelFormat::BGRX8888 {
+                expected.iter_mut().skip(3).step_by(4).for_each(|x| *x = 0);
+            }
+            if format.bytes_per_pixel == 5 {
+                expected.iter_mut().skip(3).step_by(5).for_each(|x| *x = 0);
+            }
+            assert_eq!(converted.data(), expected, "{format:?}");
+            assert_eq!(converted.to_image(), source.to_image());
+        }
+        assert!(source
+            .convert(PixelFormat::new(3, 0, 1, 2, Some(3)))
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_inconsistent_layouts() {
+        let cases = [
+            (2, 2, 8, PixelFormat::RGBA8888, 16, true),
+            // The last row needs no padding.
+            (2, 2, 12, PixelFormat::RGBA8888, 20, true),
+            (0, 0, 0, PixelFormat::RGBA8888, 0, true),
+            (2, 0, 8, PixelFormat::RGBA8888, 0, true),
+            (2, 2, 8, PixelFormat::RGBA8888, 15, false),
+            (2, 2, 12, PixelFormat::RGBA8888, 19, false),
+            (2, 2, 7, PixelFormat::RGBA8888, 16, false),
+            (2, 2, 6, PixelFormat::new(3, 0, 1, 3, None), 12, false),
+        ];
+        for (width, height, stride, format, len, valid) in cases {
+            let result = RawFrame::new(width, height, stride, format, vec![0; len]);
+            assert_eq!(
+                result.is_ok(),
+                valid,
+                "{width}x{height} stride {stride} len {len}"
+            );
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use super::{Area, ScreenId};
+
+/// Rotation of a screen relative to its native orientation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rotation {
+    #[default]
+    Normal,
+    Left,
+    Inverted,
+    Right,
+}
+
+/// Description of a monitor that can be passed to a `ScreenCaptureProvider`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Screen {
+    pub id: ScreenId,
+    /// Connector or monitor name as reported by the platform, e.g. `DP-1`.
+    pub name: String,
+    /// Position and size in global desktop coordinates.
+    pub area: Area,
+    pub rotation: Rotation,
+    pub primary: bool,
+    /// Refresh rate in Hz, if known.
+    pub refresh_rate: Option<f64>,
+    pub scale_factor: f64,
+}
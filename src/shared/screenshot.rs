@@ -0,0 +1,9 @@
+use super::CaptureMetadata;
+use crate::ImageBuffer;
+
+/// A captured image together with its [`CaptureMetadata`].
+#[derive(Debug, Clone)]
+pub struct Screenshot {
+    pub image: ImageBuffer,
+    pub metadata: CaptureMetadata,
+}
@@ -0,0 +1,14 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Identifies a stream of captures, so backends can track changes for each stream separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreamId(u64);
+
+impl StreamId {
+    /// Returns an id that no other stream in this process uses.
+    pub fn unique() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        StreamId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
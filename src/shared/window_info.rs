@@ -0,0 +1,22 @@
+use super::{Area, WindowId};
+
+/// Description of a top-level window that can be passed to a `WindowCaptureProvider`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowInfo {
+    pub id: WindowId,
+    pub title: String,
+    /// Application class, e.g. the class part of `WM_CLASS` on X11.
+    pub class: Option<String>,
+    /// Application instance name, e.g. the instance part of `WM_CLASS` on X11.
+    pub instance: Option<String>,
+    pub pid: Option<u32>,
+    /// Position and size of the client area in global desktop coordinates.
+    pub area: Area,
+    /// Virtual desktop the window is placed on. `u32::MAX` means all desktops.
+    pub desktop: Option<u32>,
+    /// Whether the window is mapped, even if an ancestor is not.
+    pub mapped: bool,
+    /// Whether the window is viewable and not minimized.
+    pub visible: bool,
+}
@@ -0,0 +1,394 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    error::Result,
+    shared::{Area, CaptureTarget, StreamId},
+    traits::CaptureBackend,
+    ImageBuffer,
+};
+
+/// A frame captured by a [`FrameStream`].
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub image: ImageBuffer,
+    /// Monotonic time since the stream started, taken right before the capture.
+    pub timestamp: Duration,
+    /// Number of frames yielded before this one.
+    pub sequence: u64,
+    /// Frame slots missed since the previous frame because capturing or consuming was too slow.
+    pub dropped: u64,
+    /// Regions that changed since the previous frame, in image coordinates. Covers the whole
+    /// image for the first frame and for backends that do not track changes.
+    pub dirty: Vec<Area>,
+}
+
+/// Repeatedly captures a target at a fixed frame rate.
+///
+/// Iterating blocks until the next frame is due. Slots that could not be served in time are
+/// skipped and reported through [`Frame::dropped`]; failed captures are yielded as errors without
+/// ending the stream.
+pub struct FrameStream<P: CaptureBackend> {
+    // Only taken by `into_inner`.
+    provider: Option<P>,
+    id: StreamId,
+    target: CaptureTarget,
+    pacer: Pacer,
+    started: Instant,
+    sequence: u64,
+    pending_dropped: u64,
+    total_dropped: u64,
+    spare: Option<ImageBuffer>,
+}
+
+impl<P: CaptureBackend> FrameStream<P> {
+    /// Creates a stream capturing `target` at `fps` frames per second. A rate of zero captures
+    /// as fast as possible.
+    pub fn new(provider: P, target: CaptureTarget, fps: f64) -> Self {
+        FrameStream {
+            provider: Some(provider),
+            id: StreamId::unique(),
+            target,
+            pacer: Pacer::new(fps),
+            started: Instant::now(),
+            sequence: 0,
+            pending_dropped: 0,
+            total_dropped: 0,
+            spare: None,
+        }
+    }
+
+    pub fn target(&self) -> CaptureTarget {
+        self.target
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.pacer.interval()
+    }
+
+    /// Total number of frame slots dropped so far.
+    pub fn dropped(&self) -> u64 {
+        self.total_dropped
+    }
+
+    pub fn provider(&self) -> &P {
+        self.provider
+            .as_ref()
+            .expect("provider is present until into_inner")
+    }
+
+    /// Hands a frame's image back so a later capture can reuse its allocation.
+    pub fn recycle(&mut self, image: ImageBuffer) {
+        self.spare = Some(image);
+    }
+
+    /// Ends the stream and returns its provider.
+    pub fn into_inner(mut self) -> P {
+        let provider = self
+            .provider
+            .take()
+            .expect("provider is present until into_inner");
+        provider.end_stream(self.id);
+        provider
+    }
+
+    /// Waits for the next slot and returns the number of slots missed before it.
+    fn wait(&mut self) -> u64 {
+        let (slot, missed) = self.pacer.next_slot(Instant::now());
+        let now = Instant::now();
+        if now < slot {
+            std::thread::sleep(slot - now);
+        }
+        missed
+    }
+}
+
+/// Frame slots at a fixed rate, shared by the blocking and async streams.
+pub(crate) struct Pacer {
+    interval: Duration,
+    next_due: Option<Instant>,
+}
+
+impl Pacer {
+    /// Longest interval between slots. Far beyond any practical rate, but still safe to add to an
+    /// `Instant`.
+    const MAX_INTERVAL: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+    pub(crate) fn new(fps: f64) -> Self {
+        let interval = if fps.is_finite() && fps > 0.0 {
+            Duration::try_from_secs_f64(1.0 / fps).map_or(Self::MAX_INTERVAL, |interval| {
+                interval.min(Self::MAX_INTERVAL)
+            })
+        } else {
+            Duration::ZERO
+        };
+        Pacer {
+            interval,
+            next_due: None,
+        }
+    }
+
+    pub(crate) fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns when the next frame is due and the number of slots missed before it. Late frames
+    /// are due right away.
+    pub(crate) fn next_slot(&mut self, now: Instant) -> (Instant, u64) {
+        let Some(due) = self.next_due else {
+            self.next_due = Some(now + self.interval);
+            return (now, 0);
+        };
+        if now < due {
+            self.next_due = Some(due + self.interval);
+            return (due, 0);
+        }
+        if self.interval.is_zero() {
+            self.next_due = Some(now);
+            return (now, 0);
+        }
+        let missed = ((now - due).as_nanos() / self.interval.as_nanos()) as u64;
+        let skipped = self.interval.as_nanos() * (missed as u128 + 1);
+        self.next_due = Some(due + Duration::from_nanos(skipped.min(u64::MAX as u128) as u64));
+        (now, missed)
+    }
+}
+
+impl<P: CaptureBackend> Iterator for FrameStream<P> {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pending_dropped += self.wait();
+        let timestamp = self.started.elapsed();
+        let mut image = self.spare.take().unwrap_or_else(|| ImageBuffer::new(0, 0));
+        let dirty = match self
+            .provider()
+            .capture_changes(self.id, &self.target, &mut image)
+        {
+            Ok(dirty) => dirty,
+            Err(error) => {
+                self.spare = Some(image);
+                return Some(Err(error));
+            }
+        };
+
+        let frame = Frame {
+            image,
+            timestamp,
+            sequence: self.sequence,
+            dropped: std::mem::take(&mut self.pending_dropped),
+            dirty,
+        };
+        self.sequence += 1;
+        self.total_dropped += frame.dropped;
+        Some(Ok(frame))
+    }
+}
+
+impl<P: CaptureBackend> Drop for FrameStream<P> {
+    fn drop(&mut self) {
+        if let Some(provider) = &self.provider {
+            provider.end_stream(self.id);
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+pub use self::async_stream::AsyncFrameStream;
+
+#[cfg(feature = "stream")]
+mod async_stream {
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+
+    use futures_core::Stream;
+
+    use super::{Frame, FrameStream};
+    use crate::{error::Result, traits::CaptureBackend};
+
+    #[derive(Default)]
+    struct Shared {
+        waker: Mutex<Option<Waker>>,
+        stopped: AtomicBool,
+    }
+
+    /// The capture thread's end of the channel. Dropping it, also while unwinding from a
+    /// panicking provider, disconnects the channel and then wakes the consumer, so a poll that
+    /// already returned `Pending` observes the end of the stream.
+    struct FrameSender {
+        sender: Option<SyncSender<Result<Frame>>>,
+        shared: Arc<Shared>,
+    }
+
+    impl FrameSender {
+        /// Returns `false` once the consumer is gone.
+        fn send(&self, frame: Result<Frame>) -> bool {
+            let sent = self
+                .sender
+                .as_ref()
+                .is_some_and(|sender| sender.send(frame).is_ok());
+            if sent {
+                self.wake();
+            }
+            sent
+        }
+
+        fn wake(&self) {
+            let waker = self
+                .shared
+                .waker
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .take();
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+    }
+
+    impl Drop for FrameSender {
+        fn drop(&mut self) {
+            self.sender = None;
+            self.wake();
+        }
+    }
+
+    /// A [`FrameStream`] driven by a dedicated capture thread, exposed as a `futures` `Stream`.
+    ///
+    /// At most one frame is buffered; while the consumer lags behind, the capture thread waits
+    /// and the missed slots are reported as dropped. Dropping the stream stops the thread after
+    /// its current capture.
+    pub struct AsyncFrameStream {
+        receiver: Receiver<Result<Frame>>,
+        shared: Arc<Shared>,
+    }
+
+    impl AsyncFrameStream {
+        pub fn spawn<P: CaptureBackend + 'static>(mut frames: FrameStream<P>) -> Self {
+            let (sender, receiver) = mpsc::sync_channel(1);
+            let shared = Arc::new(Shared::default());
+            let sender = FrameSender {
+                sender: Some(sender),
+                shared: shared.clone(),
+            };
+            std::thread::Builder::new()
+                .name("libscreenshot-stream".to_string())
+                .spawn(move || {
+                    while !sender.shared.stopped.load(Ordering::Relaxed) {
+                        let Some(frame) = frames.next() else { break };
+                        if !sender.send(frame) {
+                            break;
+                        }
+                    }
+                })
+                .expect("failed to spawn capture thread");
+            AsyncFrameStream { receiver, shared }
+        }
+    }
+
+    impl Stream for AsyncFrameStream {
+        type Item = Result<Frame>;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            match self.receiver.try_recv() {
+                Ok(frame) => return Poll::Ready(Some(frame)),
+                Err(TryRecvError::Disconnected) => return Poll::Ready(None),
+                Err(TryRecvError::Empty) => {}
+            }
+            *self.shared.waker.lock().unwrap_or_else(|e| e.into_inner()) = Some(cx.waker().clone());
+            // A frame may have arrived before the waker was registered.
+            match self.receiver.try_recv() {
+                Ok(frame) => Poll::Ready(Some(frame)),
+                Err(TryRecvError::Disconnected) => Poll::Ready(None),
+                Err(TryRecvError::Empty) => Poll::Pending,
+            }
+        }
+    }
+
+    impl Drop for AsyncFrameStream {
+        fn drop(&mut self) {
+            self.shared.stopped.store(true, Ordering::Relaxed);
+        }
+    }
+
+    impl<P: CaptureBackend + 'static> FrameStream<P> {
+        /// Moves the stream onto its own capture thread.
+        pub fn into_async(self) -> AsyncFrameStream {
+            AsyncFrameStream::spawn(self)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn pacer_counts_missed_slots() {
+        let start = Instant::now();
+        let mut pacer = Pacer::new(100.0);
+        assert_eq!(pacer.interval(), ms(10));
+        assert_eq!(pacer.next_slot(start), (start, 0));
+        assert_eq!(pacer.next_slot(start), (start + ms(10), 0));
+        assert_eq!(pacer.next_slot(start + ms(5)), (start + ms(20), 0));
+        // Due at 30, so the slots at 30, 40 and 50 are missed and 60 is taken late.
+        assert_eq!(pacer.next_slot(start + ms(65)), (start + ms(65), 3));
+        assert_eq!(pacer.next_slot(start + ms(66)), (start + ms(70), 0));
+        // Exactly on time.
+        assert_eq!(pacer.next_slot(start + ms(80)), (start + ms(80), 0));
+        assert_eq!(pacer.next_slot(start + ms(80)), (start + ms(90), 0));
+    }
+
+    #[test]
+    fn unpaced_streams_never_miss_slots() {
+        let start = Instant::now();
+        for fps in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let mut pacer = Pacer::new(fps);
+            assert_eq!(pacer.interval(), Duration::ZERO);
+            assert_eq!(pacer.next_slot(start), (start, 0));
+            assert_eq!(pacer.next_slot(start + ms(500)), (start + ms(500), 0));
+        }
+    }
+
+    #[test]
+    fn tiny_rates_are_clamped() {
+        let start = Instant::now();
+        for fps in [1e-20, 1e-300, f64::MIN_POSITIVE] {
+            let mut pacer = Pacer::new(fps);
+            assert_eq!(pacer.interval(), Pacer::MAX_INTERVAL);
+            assert_eq!(pacer.next_slot(start), (start, 0));
+            assert_eq!(pacer.next_slot(start), (start + Pacer::MAX_INTERVAL, 0));
+        }
+    }
+
+    #[cfg(all(feature = "stream", feature = "mock"))]
+    #[test]
+    fn async_stream_ends_when_the_capture_thread_stops() {
+        use crate::platform::mock::MockProvider;
+        use crate::shared::CaptureTarget;
+        use crate::traits::Provider;
+        use futures_core::Stream;
+        use std::pin::Pin;
+
+        // The latency keeps the first poll pending until the provider panics.
+        let provider = MockProvider::new()
+            .with_latency(ms(50))
+            .with_fault(|_| panic!("backend panicked"));
+        let mut frames = FrameStream::new(provider, CaptureTarget::Full, 0.0).into_async();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            for _ in 0..2 {
+                let next = std::future::poll_fn(|cx| Pin::new(&mut frames).poll_next(cx));
+                assert!(next.await.is_none());
+            }
+        });
+    }
+}
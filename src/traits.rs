@@ -1,12 +1,18 @@
 mod area_capture_provider;
+mod capture_backend;
 mod full_capture_provider;
 mod screen_capture_provider;
+mod screen_enumeration_provider;
 mod window_capture_provider;
+mod window_enumeration_provider;
 
 pub use self::area_capture_provider::*;
+pub use self::capture_backend::*;
 pub use self::full_capture_provider::*;
 pub use self::screen_capture_provider::*;
+pub use self::screen_enumeration_provider::*;
 pub use self::window_capture_provider::*;
+pub use self::window_enumeration_provider::*;
 
 pub trait Provider
 where
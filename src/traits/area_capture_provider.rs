@@ -3,3 +3,15 @@ use crate::{error::Result, shared::Area, ImageBuffer};
 pub trait AreaCaptureProvider {
     fn capture_area(&self, area: Area) -> Result<ImageBuffer>;
 }
+
+impl<T: AreaCaptureProvider + ?Sized> AreaCaptureProvider for Box<T> {
+    fn capture_area(&self, area: Area) -> Result<ImageBuffer> {
+        (**self).capture_area(area)
+    }
+}
+
+impl<T: AreaCaptureProvider + ?Sized> AreaCaptureProvider for &T {
+    fn capture_area(&self, area: Area) -> Result<ImageBuffer> {
+        (**self).capture_area(area)
+    }
+}
@@ -0,0 +1,352 @@
+use std::time::SystemTime;
+
+use crate::{
+    error::{Error, Result},
+    shared::{
+        Area, Capabilities, Capability, CaptureMetadata, CaptureOptions, CaptureTarget,
+        CursorImage, DesktopCapture, RawFrame, Screenshot, StreamId,
+    },
+    ImageBuffer,
+};
+
+use super::{
+    AreaCaptureProvider, FullCaptureProvider, ScreenCaptureProvider, ScreenEnumerationProvider,
+    WindowCaptureProvider, WindowEnumerationProvider,
+};
+
+/// Object-safe combination of all provider traits, so a backend can be held as
+/// `Box<dyn CaptureBackend>` and swapped at runtime.
+///
+/// Operations a backend does not support return [`Error::Unsupported`](crate::error::Error).
+pub trait CaptureBackend:
+    WindowCaptureProvider
+    + ScreenCaptureProvider
+    + AreaCaptureProvider
+    + FullCaptureProvider
+    + WindowEnumerationProvider
+    + ScreenEnumerationProvider
+    + Send
+    + Sync
+{
+    /// Short backend name, e.g. `"x11"`.
+    fn name(&self) -> &'static str;
+
+    fn capabilities(&self) -> Capabilities;
+
+    /// Connects to the display server ahead of the first capture, failing if it is unreachable
+    /// or lacks the required extensions.
+    fn connect(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn capture(&self, target: &CaptureTarget) -> Result<ImageBuffer> {
+        match *target {
+            CaptureTarget::Window(window_id) => self.capture_window(window_id),
+            CaptureTarget::FocusedWindow => self.capture_focused_window(),
+            CaptureTarget::Screen(screen_id) => self.capture_screen(screen_id),
+            CaptureTarget::CurrentScreen => self.capture_current_screen(),
+            CaptureTarget::Area(area) => self.capture_area(area),
+            CaptureTarget::Full => self.capture_full(),
+            // Only backends that can locate the pointer resolve this target.
+            CaptureTarget::WindowUnderCursor => match self.resolve_target(target)? {
+                CaptureTarget::Window(window_id) => self.capture_window(window_id),
+                _ => Err(Error::Unsupported(Capability::Cursor)),
+            },
+        }
+    }
+
+    /// Resolves targets that depend on the current state, such as the focused window, to a
+    /// concrete window or screen. Returns the target unchanged if the backend cannot tell.
+    fn resolve_target(&self, target: &CaptureTarget) -> Result<CaptureTarget> {
+        Ok(*target)
+    }
+
+    /// Captures `target` together with a description of what was captured.
+    ///
+    /// By default the description is assembled from the enumeration providers right before the
+    /// capture, so it describes the state the capture was taken in; the position is `(0, 0)`
+    /// where it cannot be determined.
+    fn capture_with_metadata(&self, target: &CaptureTarget) -> Result<Screenshot> {
+        let target = self.resolve_target(target)?;
+        let screens = self.list_screens().unwrap_or_default();
+        let window = match target {
+            CaptureTarget::Window(window_id) => self
+                .list_windows()
+                .ok()
+                .and_then(|windows| windows.into_iter().find(|window| window.id == window_id)),
+            _ => None,
+        };
+        let timestamp = SystemTime::now();
+        let image = self.capture(&target)?;
+        let origin = match target {
+            CaptureTarget::Window(_) => {
+                window.as_ref().map(|window| (window.area.x, window.area.y))
+            }
+            CaptureTarget::Screen(screen_id) => screens
+                .iter()
+                .find(|screen| screen.id == screen_id)
+                .map(|screen| (screen.area.x, screen.area.y)),
+            CaptureTarget::Area(area) => Some((area.x, area.y)),
+            CaptureTarget::Full => screens
+                .iter()
+                .map(|screen| screen.area.x)
+                .min()
+                .zip(screens.iter().map(|screen| screen.area.y).min()),
+            CaptureTarget::FocusedWindow
+            | CaptureTarget::CurrentScreen
+            | CaptureTarget::WindowUnderCursor => None,
+        };
+        let (x, y) = origin.unwrap_or((0, 0));
+        let area = Area::new(x, y, image.width() as u64, image.height() as u64);
+        let metadata = CaptureMetadata::new(
+            target.kind(),
+            area,
+            window,
+            &screens,
+            self.name(),
+            timestamp,
+        );
+        Ok(Screenshot { image, metadata })
+    }
+
+    /// Captures `target` with `options` on top of the backend's own configuration.
+    ///
+    /// By default the pointer is drawn from [`capture_cursor`](Self::capture_cursor), and
+    /// decorations are only accepted from backends that include them anyway.
+    fn capture_with_options(
+        &self,
+        target: &CaptureTarget,
+        options: &CaptureOptions,
+    ) -> Result<Screenshot> {
+        if options.decorations && !self.capabilities().decorations {
+            return Err(Error::Unsupported(Capability::Decorations));
+        }
+        let mut screenshot = self.capture_with_metadata(target)?;
+        if options.cursor {
+            let area = screenshot.metadata.area;
+            self.capture_cursor()?
+                .draw_onto(&mut screenshot.image, area.x, area.y);
+        }
+        Ok(screenshot)
+    }
+
+    /// Captures `target` into `buffer`, reusing its allocation where the backend supports it.
+    fn capture_into(&self, target: &CaptureTarget, buffer: &mut ImageBuffer) -> Result<()> {
+        *buffer = self.capture(target)?;
+        Ok(())
+    }
+
+    /// Captures `target` into `buffer` and returns the regions that changed since the previous
+    /// call for the same `stream` and target, in image coordinates.
+    ///
+    /// Changes are tracked for every stream separately, so several streams can share a backend.
+    /// Backends that do not track changes report the whole image.
+    fn capture_changes(
+        &self,
+        _stream: StreamId,
+        target: &CaptureTarget,
+        buffer: &mut ImageBuffer,
+    ) -> Result<Vec<Area>> {
+        self.capture_into(target, buffer)?;
+        Ok(vec![Area::new(
+            0,
+            0,
+            buffer.width() as u64,
+            buffer.height() as u64,
+        )])
+    }
+
+    /// Releases what the backend keeps to track changes for `stream`. Streams call this when
+    /// they are dropped.
+    fn end_stream(&self, _stream: StreamId) {}
+
+    /// Captures `target` in the backend's native pixel format, copying the pixels at most once.
+    ///
+    /// Raw frames show the target as the backend reads it, without the pointer drawn in. By
+    /// default the RGBA image from [`capture`](Self::capture) is handed over.
+    fn capture_raw(&self, target: &CaptureTarget) -> Result<RawFrame<'static>> {
+        self.capture(target).map(RawFrame::from)
+    }
+
+    /// Like [`capture_raw`](Self::capture_raw), but lends the frame to `f` instead, so backends
+    /// can expose their own buffer without copying it. `f` must not call back into the backend.
+    fn capture_raw_with(
+        &self,
+        target: &CaptureTarget,
+        f: &mut dyn FnMut(RawFrame<'_>),
+    ) -> Result<()> {
+        f(self.capture_raw(target)?);
+        Ok(())
+    }
+
+    /// Returns the mouse pointer image and position, for callers that draw it themselves.
+    fn capture_cursor(&self) -> Result<CursorImage> {
+        Err(Error::Unsupported(Capability::Cursor))
+    }
+
+    /// Captures the whole desktop together with the monitor layout.
+    ///
+    /// By default the full capture is assumed to start at the top-left corner of the screens.
+    fn capture_desktop(&self) -> Result<DesktopCapture> {
+        let image = self.capture_full()?;
+        let screens = self.list_screens().unwrap_or_default();
+        let x = screens
+            .iter()
+            .map(|screen| screen.area.x)
+            .min()
+            .unwrap_or(0);
+        let y = screens
+            .iter()
+            .map(|screen| screen.area.y)
+            .min()
+            .unwrap_or(0);
+        let bounds = Area::new(x, y, image.width() as u64, image.height() as u64);
+        Ok(DesktopCapture {
+            image,
+            bounds,
+            screens,
+        })
+    }
+}
+
+impl<T: CaptureBackend + ?Sized> CaptureBackend for Box<T> {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        (**self).capabilities()
+    }
+
+    fn connect(&self) -> Result<()> {
+        (**self).connect()
+    }
+
+    fn capture(&self, target: &CaptureTarget) -> Result<ImageBuffer> {
+        (**self).capture(target)
+    }
+
+    fn resolve_target(&self, target: &CaptureTarget) -> Result<CaptureTarget> {
+        (**self).resolve_target(target)
+    }
+
+    fn capture_with_metadata(&self, target: &CaptureTarget) -> Result<Screenshot> {
+        (**self).capture_with_metadata(target)
+    }
+
+    fn capture_with_options(
+        &self,
+        target: &CaptureTarget,
+        options: &CaptureOptions,
+    ) -> Result<Screenshot> {
+        (**self).capture_with_options(target, options)
+    }
+
+    fn capture_into(&self, target: &CaptureTarget, buffer: &mut ImageBuffer) -> Result<()> {
+        (**self).capture_into(target, buffer)
+    }
+
+    fn capture_changes(
+        &self,
+        stream: StreamId,
+        target: &CaptureTarget,
+        buffer: &mut ImageBuffer,
+    ) -> Result<Vec<Area>> {
+        (**self).capture_changes(stream, target, buffer)
+    }
+
+    fn end_stream(&self, stream: StreamId) {
+        (**self).end_stream(stream)
+    }
+
+    fn capture_raw(&self, target: &CaptureTarget) -> Result<RawFrame<'static>> {
+        (**self).capture_raw(target)
+    }
+
+    fn capture_raw_with(
+        &self,
+        target: &CaptureTarget,
+        f: &mut dyn FnMut(RawFrame<'_>),
+    ) -> Result<()> {
+        (**self).capture_raw_with(target, f)
+    }
+
+    fn capture_cursor(&self) -> Result<CursorImage> {
+        (**self).capture_cursor()
+    }
+
+    fn capture_desktop(&self) -> Result<DesktopCapture> {
+        (**self).capture_desktop()
+    }
+}
+
+impl<T: CaptureBackend + ?Sized> CaptureBackend for &T {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        (**self).capabilities()
+    }
+
+    fn connect(&self) -> Result<()> {
+        (**self).connect()
+    }
+
+    fn capture(&self, target: &CaptureTarget) -> Result<ImageBuffer> {
+        (**self).capture(target)
+    }
+
+    fn resolve_target(&self, target: &CaptureTarget) -> Result<CaptureTarget> {
+        (**self).resolve_target(target)
+    }
+
+    fn capture_with_metadata(&self, target: &CaptureTarget) -> Result<Screenshot> {
+        (**self).capture_with_metadata(target)
+    }
+
+    fn capture_with_options(
+        &self,
+        target: &CaptureTarget,
+        options: &CaptureOptions,
+    ) -> Result<Screenshot> {
+        (**self).capture_with_options(target, options)
+    }
+
+    fn capture_into(&self, target: &CaptureTarget, buffer: &mut ImageBuffer) -> Result<()> {
+        (**self).capture_into(target, buffer)
+    }
+
+    fn capture_changes(
+        &self,
+        stream: StreamId,
+        target: &CaptureTarget,
+        buffer: &mut ImageBuffer,
+    ) -> Result<Vec<Area>> {
+        (**self).capture_changes(stream, target, buffer)
+    }
+
+    fn end_stream(&self, stream: StreamId) {
+        (**self).end_stream(stream)
+    }
+
+    fn capture_raw(&self, target: &CaptureTarget) -> Result<RawFrame<'static>> {
+        (**self).capture_raw(target)
+    }
+
+    fn capture_raw_with(
+        &self,
+        target: &CaptureTarget,
+        f: &mut dyn FnMut(RawFrame<'_>),
+    ) -> Result<()> {
+        (**self).capture_raw_with(target, f)
+    }
+
+    fn capture_cursor(&self) -> Result<CursorImage> {
+        (**self).capture_cursor()
+    }
+
+    fn capture_desktop(&self) -> Result<DesktopCapture> {
+        (**self).capture_desktop()
+    }
+}
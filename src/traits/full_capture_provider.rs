@@ -3,3 +3,15 @@ use crate::{error::Result, ImageBuffer};
 pub trait FullCaptureProvider {
     fn capture_full(&self) -> Result<ImageBuffer>;
 }
+
+impl<T: FullCaptureProvider + ?Sized> FullCaptureProvider for Box<T> {
+    fn capture_full(&self) -> Result<ImageBuffer> {
+        (**self).capture_full()
+    }
+}
+
+impl<T: FullCaptureProvider + ?Sized> FullCaptureProvider for &T {
+    fn capture_full(&self) -> Result<ImageBuffer> {
+        (**self).capture_full()
+    }
+}
@@ -4,3 +4,23 @@ pub trait ScreenCaptureProvider {
     fn capture_screen(&self, screen_id: ScreenId) -> Result<ImageBuffer>;
     fn capture_current_screen(&self) -> Result<ImageBuffer>;
 }
+
+impl<T: ScreenCaptureProvider + ?Sized> ScreenCaptureProvider for Box<T> {
+    fn capture_screen(&self, screen_id: ScreenId) -> Result<ImageBuffer> {
+        (**self).capture_screen(screen_id)
+    }
+
+    fn capture_current_screen(&self) -> Result<ImageBuffer> {
+        (**self).capture_current_screen()
+    }
+}
+
+impl<T: ScreenCaptureProvider + ?Sized> ScreenCaptureProvider for &T {
+    fn capture_screen(&self, screen_id: ScreenId) -> Result<ImageBuffer> {
+        (**self).capture_screen(screen_id)
+    }
+
+    fn capture_current_screen(&self) -> Result<ImageBuffer> {
+        (**self).capture_current_screen()
+    }
+}
@@ -0,0 +1,17 @@
+use crate::{error::Result, shared::Screen};
+
+pub trait ScreenEnumerationProvider {
+    fn list_screens(&self) -> Result<Vec<Screen>>;
+}
+
+impl<T: ScreenEnumerationProvider + ?Sized> ScreenEnumerationProvider for Box<T> {
+    fn list_screens(&self) -> Result<Vec<Screen>> {
+        (**self).list_screens()
+    }
+}
+
+impl<T: ScreenEnumerationProvider + ?Sized> ScreenEnumerationProvider for &T {
+    fn list_screens(&self) -> Result<Vec<Screen>> {
+        (**self).list_screens()
+    }
+}
@@ -4,3 +4,23 @@ pub trait WindowCaptureProvider {
     fn capture_window(&self, window_id: WindowId) -> Result<ImageBuffer>;
     fn capture_focused_window(&self) -> Result<ImageBuffer>;
 }
+
+impl<T: WindowCaptureProvider + ?Sized> WindowCaptureProvider for Box<T> {
+    fn capture_window(&self, window_id: WindowId) -> Result<ImageBuffer> {
+        (**self).capture_window(window_id)
+    }
+
+    fn capture_focused_window(&self) -> Result<ImageBuffer> {
+        (**self).capture_focused_window()
+    }
+}
+
+impl<T: WindowCaptureProvider + ?Sized> WindowCaptureProvider for &T {
+    fn capture_window(&self, window_id: WindowId) -> Result<ImageBuffer> {
+        (**self).capture_window(window_id)
+    }
+
+    fn capture_focused_window(&self) -> Result<ImageBuffer> {
+        (**self).capture_focused_window()
+    }
+}
@@ -0,0 +1,18 @@
+use crate::{error::Result, shared::WindowInfo};
+
+pub trait WindowEnumerationProvider {
+    /// Lists all top-level windows in stacking order, from bottom to top.
+    fn list_windows(&self) -> Result<Vec<WindowInfo>>;
+}
+
+impl<T: WindowEnumerationProvider + ?Sized> WindowEnumerationProvider for Box<T> {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>> {
+        (**self).list_windows()
+    }
+}
+
+impl<T: WindowEnumerationProvider + ?Sized> WindowEnumerationProvider for &T {
+    fn list_windows(&self) -> Result<Vec<WindowInfo>> {
+        (**self).list_windows()
+    }
+}